@@ -95,7 +95,7 @@ fn lex_token() -> impl Parser<char, Token, Error = Cheap<char>> {
             .to(TokenKind::Annotate),
     ));
 
-    let control = one_of("></%=+-*[]().,:|!{}").map(TokenKind::Control);
+    let control = one_of("></%=+-*[]().,:;|!{}").map(TokenKind::Control);
 
     let ident = ident_part().map(TokenKind::Ident);
 
@@ -212,47 +212,32 @@ pub(crate) fn ident_part() -> impl Parser<char, String, Error = Cheap<char>> + C
 }
 
 fn literal() -> impl Parser<char, Literal, Error = Cheap<char>> {
-    let binary_notation = just("0b")
-        .then_ignore(just("_").or_not())
-        .ignore_then(
-            filter(|c: &char| *c == '0' || *c == '1')
-                .repeated()
-                .at_least(1)
-                .at_most(32)
-                .collect::<String>()
-                .try_map(|digits, _| {
-                    Ok(Literal::Integer(i64::from_str_radix(&digits, 2).unwrap()))
-                }),
-        )
-        .labelled("number");
-
-    let hexadecimal_notation = just("0x")
-        .then_ignore(just("_").or_not())
-        .ignore_then(
-            filter(|c: &char| c.is_ascii_hexdigit())
-                .repeated()
-                .at_least(1)
-                .at_most(12)
-                .collect::<String>()
-                .try_map(|digits, _| {
-                    Ok(Literal::Integer(i64::from_str_radix(&digits, 16).unwrap()))
-                }),
-        )
-        .labelled("number");
-
-    let octal_notation = just("0o")
-        .then_ignore(just("_").or_not())
-        .ignore_then(
-            filter(|&c| ('0'..='7').contains(&c))
-                .repeated()
-                .at_least(1)
-                .at_most(12)
-                .collect::<String>()
-                .try_map(|digits, _| {
-                    Ok(Literal::Integer(i64::from_str_radix(&digits, 8).unwrap()))
-                }),
-        )
-        .labelled("number");
+    // Digits can be separated by underscores, as in `0xFFFF_FFFF`.
+    //
+    // Invalid digits are reported with `validate` rather than `try_map`, so
+    // the parser doesn't backtrack after the prefix and lex
+    // `0x8000_0000_0000_0000` as `0` followed by an ident.
+    let radix_notation = |prefix: &'static str, radix: u32| {
+        just(prefix)
+            .ignore_then(
+                filter(move |c: &char| c.is_digit(radix) || *c == '_')
+                    .repeated()
+                    .collect::<String>()
+                    .validate(move |digits, span, emit| {
+                        i64::from_str_radix(&digits.replace('_', ""), radix)
+                            .map(Literal::Integer)
+                            .unwrap_or_else(|_| {
+                                emit(Cheap::expected_input_found(span, None, None));
+                                Literal::Integer(0)
+                            })
+                    }),
+            )
+            .labelled("number")
+    };
+
+    let binary_notation = radix_notation("0b", 2);
+    let hexadecimal_notation = radix_notation("0x", 16);
+    let octal_notation = radix_notation("0o", 8);
 
     let exp = one_of("eE").chain(one_of("+-").or_not().chain::<char, _, _>(text::digits(10)));
 
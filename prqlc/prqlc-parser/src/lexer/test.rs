@@ -68,8 +68,28 @@ fn numbers() {
         Literal::Integer(3735928559)
     );
 
+    assert_eq!(
+        literal().parse("0xFFFF_FFFF").unwrap(),
+        Literal::Integer(4294967295)
+    );
+
     // Octal notation
     assert_eq!(literal().parse("0o777").unwrap(), Literal::Integer(511));
+
+    // Too large for an integer
+    assert!(literal().parse("0x8000_0000_0000_0000").is_err());
+
+    // A prefix without digits
+    assert!(literal().parse("0x").is_err());
+    assert!(literal().parse("0b2").is_err());
+
+    // Decimal notation
+    assert_eq!(
+        literal().parse("1_000_000").unwrap(),
+        Literal::Integer(1000000)
+    );
+    assert_eq!(literal().parse("1.5e6").unwrap(), Literal::Float(1500000.0));
+    assert_eq!(literal().parse("2.5E-3").unwrap(), Literal::Float(0.0025));
 }
 
 #[test]
@@ -187,6 +207,24 @@ fn range() {
     "#);
 }
 
+#[test]
+fn unicode_idents() {
+    // Idents start with a letter or `_`, and continue with letters, digits or
+    // `_`, which may be non-ASCII
+    assert_debug_snapshot!(Tokens(lexer().parse("名前 größe2 _ü").unwrap()), @r#"
+    Tokens(
+        [
+            0..2: Ident("名前"),
+            3..9: Ident("größe2"),
+            10..12: Ident("_ü"),
+        ],
+    )
+    "#);
+
+    // A non-ASCII digit can't start an ident
+    assert!(lexer().parse("٣x").is_err());
+}
+
 #[test]
 fn test_lex_source() {
     use insta::assert_debug_snapshot;
@@ -0,0 +1,69 @@
+//! Comments are dropped before parsing, since they can appear between any two
+//! tokens. Instead, they are extracted from the tokens beforehand and attached
+//! to the statements they are within or precede.
+
+use crate::lexer::lr::{Token, TokenKind};
+use crate::parser::pr::{Comment, Stmt, StmtKind};
+use crate::span::Span;
+
+pub(super) fn extract_comments(tokens: &[Token], source_id: u16) -> Vec<Comment> {
+    let mut comments = Vec::new();
+    let mut prev: Option<&TokenKind> = None;
+    for token in tokens {
+        if let TokenKind::Comment(text) = &token.kind {
+            let trailing = !matches!(prev, None | Some(TokenKind::NewLine | TokenKind::Start));
+
+            comments.push(Comment {
+                text: text.clone(),
+                span: Span {
+                    start: token.span.start,
+                    end: token.span.end,
+                    source_id,
+                },
+                trailing,
+            });
+        }
+        prev = Some(&token.kind);
+    }
+    comments
+}
+
+/// Attaches each comment to a statement:
+/// - a trailing comment to the last statement starting before it,
+/// - any other comment to the first statement ending after it.
+///
+/// Comments within a module are attached to statements of that module.
+pub(super) fn attach_comments(stmts: &mut [Stmt], comments: Vec<Comment>) {
+    for comment in comments {
+        attach_comment(stmts, comment);
+    }
+}
+
+fn attach_comment(stmts: &mut [Stmt], comment: Comment) {
+    let start = comment.span.start;
+
+    let index = if comment.trailing {
+        stmts
+            .iter()
+            .rposition(|s| s.span.map_or(false, |span| span.start <= start))
+    } else {
+        stmts
+            .iter()
+            .position(|s| s.span.map_or(false, |span| start < span.end))
+    };
+    // comments after the last statement are attached to it
+    let Some(index) = index.or(stmts.len().checked_sub(1)) else {
+        return;
+    };
+
+    let stmt = &mut stmts[index];
+    let within = stmt
+        .span
+        .map_or(false, |span| span.start <= start && start < span.end);
+    if let StmtKind::ModuleDef(module_def) = &mut stmt.kind {
+        if within && !module_def.stmts.is_empty() {
+            return attach_comment(&mut module_def.stmts, comment);
+        }
+    }
+    stmt.comments.push(comment);
+}
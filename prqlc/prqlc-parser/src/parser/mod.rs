@@ -7,6 +7,7 @@ use crate::lexer::lr;
 use crate::lexer::lr::TokenKind;
 use crate::span::Span;
 
+mod comments;
 mod expr;
 mod interpolation;
 pub(crate) mod perror;
@@ -20,8 +21,14 @@ mod types;
 // because it logs using the logging framework in `prqlc`.
 
 pub fn parse_lr_to_pr(source_id: u16, lr: Vec<lr::Token>) -> (Option<Vec<pr::Stmt>>, Vec<Error>) {
+    let comments = comments::extract_comments(&lr, source_id);
+
     let stream = prepare_stream(lr, source_id);
-    let (pr, parse_errors) = stmt::source().parse_recovery(stream);
+    let (mut pr, parse_errors) = stmt::source().parse_recovery(stream);
+
+    if let Some(stmts) = &mut pr {
+        comments::attach_comments(stmts, comments);
+    }
 
     let errors = parse_errors.into_iter().map(|e| e.into()).collect();
     log::debug!("parse errors: {errors:?}");
@@ -37,8 +44,8 @@ pub(crate) fn prepare_stream<'a>(
 ) -> Stream<'a, lr::TokenKind, Span, impl Iterator<Item = (lr::TokenKind, Span)> + Sized + 'a> {
     let final_span = tokens.last().map(|t| t.span.end).unwrap_or(0);
 
-    // We don't want comments in the AST (but we do intend to use them as part of
-    // formatting)
+    // Comments aren't part of the grammar; `parse_lr_to_pr` attaches them to
+    // statements after parsing.
     let semantic_tokens = tokens.into_iter().filter(|token| {
         !matches!(
             token.kind,
@@ -98,6 +105,7 @@ fn into_stmt((annotations, kind): (Vec<Annotation>, StmtKind), span: Span) -> St
         span: Some(span),
         annotations,
         doc_comment: None,
+        comments: Vec::new(),
     }
 }
 
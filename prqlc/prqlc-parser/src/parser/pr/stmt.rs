@@ -16,6 +16,10 @@ pub struct QueryDef {
     pub version: Option<VersionReq>,
     #[serde(default)]
     pub other: HashMap<String, String>,
+    /// Annotations of the main pipeline such as `@owner "team-data"`, which
+    /// don't affect the compiled query.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, String>,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize, JsonSchema)]
@@ -37,6 +41,23 @@ pub struct Stmt {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub doc_comment: Option<String>,
+
+    /// Regular comments within or around the statement. They don't affect
+    /// compilation, but are kept so the formatter can write them back.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub comments: Vec<Comment>,
+}
+
+/// A `# ...` comment.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Comment {
+    /// Text following the `#`, including any leading whitespace.
+    pub text: String,
+    pub span: Span,
+
+    /// Whether the comment follows code on the same line.
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub trailing: bool,
 }
 
 impl SupportsDocComment for Stmt {
@@ -97,6 +118,7 @@ impl Stmt {
             span: None,
             annotations: Vec::new(),
             doc_comment: None,
+            comments: Vec::new(),
         }
     }
 }
@@ -39,7 +39,7 @@ fn module_contents() -> impl Parser<TokenKind, Vec<Stmt>, Error = PError> {
             .at_least(1)
             .ignore_then(
                 just(TokenKind::Annotate)
-                    .ignore_then(expr())
+                    .ignore_then(expr_call())
                     .map(|expr| Annotation {
                         expr: Box::new(expr),
                     }),
@@ -60,13 +60,18 @@ fn module_contents() -> impl Parser<TokenKind, Vec<Stmt>, Error = PError> {
             var_def(),
         )));
 
+        // A statement may be terminated by `;`, which is the only way to
+        // separate two main pipelines, since a newline would continue the
+        // first pipeline.
+        //
         // Currently doc comments need to be before the annotation; probably
         // should relax this?
         with_doc_comment(
             annotation
                 .repeated()
                 .then(stmt_kind)
-                .map_with_span(into_stmt),
+                .map_with_span(into_stmt)
+                .then_ignore(ctrl(';').or_not()),
         )
         .repeated()
     })
@@ -135,7 +140,11 @@ fn query_def() -> impl Parser<TokenKind, Stmt, Error = PError> + Clone {
                 ));
             }
 
-            Ok(StmtKind::QueryDef(Box::new(QueryDef { version, other })))
+            Ok(StmtKind::QueryDef(Box::new(QueryDef {
+                version,
+                other,
+                metadata: HashMap::new(),
+            })))
         })
         .map(|kind| (Vec::new(), kind))
         .map_with_span(into_stmt)
@@ -496,6 +505,41 @@ mod tests {
         "#);
     }
 
+    #[test]
+    fn test_stmt_separator() {
+        assert_yaml_snapshot!(parse_with_parser(r#"
+        from foo;
+        from bar;
+        "#, module_contents()).unwrap(), @r#"
+        - VarDef:
+            kind: Main
+            name: main
+            value:
+              FuncCall:
+                name:
+                  Ident: from
+                  span: "0:9-13"
+                args:
+                  - Ident: foo
+                    span: "0:14-17"
+              span: "0:9-17"
+          span: "0:0-17"
+        - VarDef:
+            kind: Main
+            name: main
+            value:
+              FuncCall:
+                name:
+                  Ident: from
+                  span: "0:27-31"
+                args:
+                  - Ident: bar
+                    span: "0:32-35"
+              span: "0:27-35"
+          span: "0:18-35"
+        "#);
+    }
+
     #[test]
     fn doc_comment_inline_module() {
         // Check the newline doesn't get eated by the `{}` of the module
@@ -2,6 +2,7 @@ use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 use glob::glob;
 use prqlc::{compile, pl_to_prql, pl_to_rq, prql_to_pl, Options};
 use std::collections::BTreeMap;
+use std::fmt::Write;
 use std::fs;
 
 type Queries = BTreeMap<String, String>;
@@ -31,6 +32,42 @@ fn bench_compile(c: &mut Criterion) {
     group.finish();
 }
 
+/// A query that declares `tables` relations and joins them all, to measure how
+/// compile time grows with the size of a project.
+fn large_query(tables: usize) -> String {
+    let mut prql = String::new();
+    for i in 0..tables {
+        writeln!(
+            prql,
+            "let t{i} = (from table_{i} | filter a > {i} | select {{id, a_{i} = a, b_{i} = b}})"
+        )
+        .unwrap();
+    }
+    prql += "from t0\n";
+    for i in 1..tables {
+        writeln!(prql, "join t{i} (t0.id == t{i}.id)").unwrap();
+    }
+    prql
+}
+
+fn bench_compile_large(c: &mut Criterion) {
+    let options = Options::default();
+    let mut group = c.benchmark_group("compile_large");
+    group.sample_size(10);
+
+    for tables in [10, 50, 100] {
+        let content = large_query(tables);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(tables),
+            &content,
+            |b, content| {
+                b.iter(|| compile(content, &options));
+            },
+        );
+    }
+    group.finish();
+}
+
 fn bench_prql_to_pl(c: &mut Criterion) {
     let queries = load_queries();
     let mut group = c.benchmark_group("prql_to_pl");
@@ -72,6 +109,7 @@ fn bench_pl_to_prql(c: &mut Criterion) {
 criterion_group!(
     benches,
     bench_compile,
+    bench_compile_large,
     bench_prql_to_pl,
     bench_pl_to_rq,
     bench_pl_to_prql
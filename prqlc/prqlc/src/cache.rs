@@ -0,0 +1,164 @@
+//! Cache of compiled queries, for applications that compile the same queries
+//! repeatedly.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::pr::{ModuleDef, StmtKind};
+use crate::{compile, pl_to_prql, prql_to_pl, ErrorMessages, Options};
+
+/// A least-recently-used cache of compiled SQL.
+///
+/// Queries are keyed by their canonical PRQL (as produced by [pl_to_prql]), so
+/// queries that differ only in whitespace, comments or parentheses share an
+/// entry. Entries are keyed separately for each set of [Options], which
+/// includes the target dialect.
+#[derive(Debug)]
+pub struct CompilerCache {
+    capacity: usize,
+    entries: HashMap<CacheKey, String>,
+
+    /// Keys from the least to the most recently used.
+    recency: VecDeque<CacheKey>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    canonical_prql: String,
+    options: String,
+}
+
+impl CompilerCache {
+    /// Creates a cache that holds at most `capacity` compiled queries.
+    pub fn new(capacity: usize) -> Self {
+        CompilerCache {
+            capacity,
+            entries: HashMap::with_capacity(capacity),
+            recency: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Compiles like [compile], returning the cached SQL when an equivalent
+    /// query has already been compiled with the same options.
+    ///
+    /// Errors are not cached.
+    pub fn compile(&mut self, prql: &str, options: &Options) -> Result<String, ErrorMessages> {
+        let mut pl = prql_to_pl(prql)?;
+        strip_comments(&mut pl);
+
+        let key = CacheKey {
+            canonical_prql: pl_to_prql(&pl)?,
+            // a `Value` sorts its keys, so the order of `params` doesn't matter
+            options: serde_json::to_value(options).unwrap().to_string(),
+        };
+
+        if let Some(sql) = self.entries.get(&key) {
+            let sql = sql.clone();
+            self.touch(&key);
+            return Ok(sql);
+        }
+
+        let sql = compile(prql, options)?;
+        self.insert(key, sql.clone());
+        Ok(sql)
+    }
+
+    /// Number of cached queries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn touch(&mut self, key: &CacheKey) {
+        if let Some(position) = self.recency.iter().position(|k| k == key) {
+            let key = self.recency.remove(position).unwrap();
+            self.recency.push_back(key);
+        }
+    }
+
+    fn insert(&mut self, key: CacheKey, sql: String) {
+        if self.capacity == 0 {
+            return;
+        }
+        while self.entries.len() >= self.capacity {
+            let evicted = self.recency.pop_front().unwrap();
+            self.entries.remove(&evicted);
+        }
+        self.recency.push_back(key.clone());
+        self.entries.insert(key, sql);
+    }
+}
+
+/// Removes comments, which [pl_to_prql] would otherwise write into the key.
+fn strip_comments(module: &mut ModuleDef) {
+    for stmt in &mut module.stmts {
+        stmt.comments.clear();
+        if let StmtKind::ModuleDef(module) = &mut stmt.kind {
+            strip_comments(module);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sql::Dialect, Target};
+
+    fn options(dialect: Dialect) -> Options {
+        Options::default()
+            .no_signature()
+            .with_target(Target::Sql(Some(dialect)))
+    }
+
+    #[test]
+    fn test_cache_hit() {
+        let mut cache = CompilerCache::new(10);
+        let opts = options(Dialect::Postgres);
+
+        let first = cache.compile("from a | take 10", &opts).unwrap();
+        let second = cache
+            .compile("from a   # the same query\ntake 10\n", &opts)
+            .unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_cache_dialects() {
+        let mut cache = CompilerCache::new(10);
+
+        let postgres = cache
+            .compile("from a | take 10", &options(Dialect::Postgres))
+            .unwrap();
+        let mssql = cache
+            .compile("from a | take 10", &options(Dialect::MsSql))
+            .unwrap();
+
+        assert_ne!(postgres, mssql);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn test_cache_eviction() {
+        let mut cache = CompilerCache::new(2);
+        let opts = options(Dialect::Generic);
+
+        cache.compile("from a", &opts).unwrap();
+        cache.compile("from b", &opts).unwrap();
+        // `a` becomes the most recently used, so `b` is evicted next
+        cache.compile("from a", &opts).unwrap();
+        cache.compile("from c", &opts).unwrap();
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache
+            .recency
+            .iter()
+            .all(|k| !k.canonical_prql.contains('b')));
+
+        assert!(cache.compile("from", &opts).is_err());
+        assert_eq!(cache.len(), 2);
+    }
+}
@@ -0,0 +1,156 @@
+//! Validation of compiled SQL against a live database.
+//!
+//! The SQL is prepared, but not executed, so the database reports tables and
+//! columns that don't exist without running the query.
+
+use anyhow::{bail, Result};
+use clap::{Parser, ValueHint};
+use regex::Regex;
+
+use prqlc::ir::pl::{self, PlVisitor};
+use prqlc::sql::Dialect;
+use prqlc::{pl_to_rq_tree, pr, prql_to_pl_tree, rq_to_sql, semantic};
+use prqlc::{Error, ErrorMessages, Options, Span, Target, WithErrorInfo};
+
+use super::read_files;
+
+#[derive(Parser, Debug, Clone)]
+pub struct CheckArgs {
+    #[arg(value_parser, default_value = "-", value_hint(ValueHint::AnyPath))]
+    pub input: clio::ClioPath,
+
+    /// Database to check against, as `sqlite://<path>` or `duckdb://<path>`
+    #[arg(long)]
+    pub connection: String,
+}
+
+pub fn run(command: &mut CheckArgs) -> Result<()> {
+    let conn = Connection::open(&command.connection)?;
+    let sources = read_files(&mut command.input)?;
+
+    let options = Options::default()
+        .with_target(Target::Sql(Some(conn.dialect())))
+        .no_signature();
+    let pl = prql_to_pl_tree(&sources)?;
+    let sql = pl_to_rq_tree(pl.clone(), &[], &[semantic::NS_DEFAULT_DB.to_string()])
+        .and_then(|rq| rq_to_sql(rq, &options))
+        .map_err(|e| e.composed(&sources))?;
+
+    let Err(message) = conn.prepare(&sql) else {
+        println!("The query is valid for {}", command.connection);
+        return Ok(());
+    };
+
+    let span = missing_name(&message).and_then(|name| find_span(pl, name));
+    let error = Error::new_simple(message)
+        .push_hint("the query was checked against the database, but not run")
+        .with_span(span);
+    Err(ErrorMessages::from(error).composed(&sources).into())
+}
+
+enum Connection {
+    Sqlite(rusqlite::Connection),
+    DuckDb(duckdb::Connection),
+}
+
+impl Connection {
+    fn open(url: &str) -> Result<Self> {
+        if let Some(path) = url.strip_prefix("sqlite://") {
+            Ok(Connection::Sqlite(rusqlite::Connection::open(path)?))
+        } else if let Some(path) = url.strip_prefix("duckdb://") {
+            Ok(Connection::DuckDb(duckdb::Connection::open(path)?))
+        } else {
+            bail!("Unsupported connection `{url}`; expected `sqlite://<path>` or `duckdb://<path>`")
+        }
+    }
+
+    fn dialect(&self) -> Dialect {
+        match self {
+            Connection::Sqlite(_) => Dialect::SQLite,
+            Connection::DuckDb(_) => Dialect::DuckDb,
+        }
+    }
+
+    /// Prepares the query, returning the message of the database on failure.
+    fn prepare(&self, sql: &str) -> Result<(), String> {
+        match self {
+            Connection::Sqlite(conn) => conn.prepare(sql).map(|_| ()).map_err(|e| match e {
+                // the message would otherwise repeat the whole SQL
+                rusqlite::Error::SqlInputError { msg, .. } => msg,
+                e => e.to_string(),
+            }),
+            Connection::DuckDb(conn) => conn.prepare(sql).map(|_| ()).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// Finds the name of the missing table or column in an error of SQLite or
+/// DuckDB.
+fn missing_name(message: &str) -> Option<&str> {
+    let re = Regex::new(concat!(
+        r"no such (?:table|column): (?:\w+\.)?(\w+)",
+        r#"|Table with name (\w+) does not exist"#,
+        r#"|Referenced column "(\w+)" not found"#,
+    ))
+    .unwrap();
+
+    let captures = re.captures(message)?;
+    captures.iter().skip(1).flatten().next().map(|m| m.as_str())
+}
+
+/// Finds the first use of a name in the PRQL, so the error can point to it.
+fn find_span(pl: pr::ModuleDef, name: &str) -> Option<Span> {
+    let module = semantic::ast_expand::expand_module_def(pl).ok()?;
+
+    let mut finder = NameFinder { name, span: None };
+    finder.visit_module_def(&module);
+    finder.span
+}
+
+struct NameFinder<'a> {
+    name: &'a str,
+    span: Option<Span>,
+}
+
+impl PlVisitor for NameFinder<'_> {
+    fn visit_expr(&mut self, expr: &pl::Expr) {
+        if self.span.is_some() {
+            return;
+        }
+        match &expr.kind {
+            pl::ExprKind::Ident(ident) if ident.name == self.name => self.span = expr.span,
+            kind => self.visit_expr_kind(kind),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prqlc::SourceTree;
+
+    use super::*;
+
+    #[test]
+    fn test_missing_name() {
+        assert_eq!(missing_name("no such table: albums"), Some("albums"));
+        assert_eq!(missing_name("no such column: a.title"), Some("title"));
+        assert_eq!(
+            missing_name("Catalog Error: Table with name albums does not exist!"),
+            Some("albums")
+        );
+        assert_eq!(
+            missing_name(r#"Binder Error: Referenced column "title" not found in FROM clause!"#),
+            Some("title")
+        );
+        assert_eq!(missing_name("syntax error"), None);
+    }
+
+    #[test]
+    fn test_find_span() {
+        let sources = SourceTree::from("# title of the album\nfrom albums\nselect {title}");
+        let pl = prql_to_pl_tree(&sources).unwrap();
+        let span = find_span(pl.clone(), "title").unwrap();
+        assert_eq!(span.start..span.end, 41..46);
+        assert_eq!(find_span(pl, "tit"), None);
+    }
+}
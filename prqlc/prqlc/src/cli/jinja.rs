@@ -41,6 +41,14 @@ pub struct JinjaContext<'a> {
 /// Parse source as Jinja template, extract all interpolations
 /// and replace them with anchors.
 pub fn pre_process(source: &str) -> Result<(String, JinjaContext)> {
+    let mut context = JinjaContext::default();
+    let anchored_source = pre_process_into(source, &mut context)?;
+    Ok((anchored_source, context))
+}
+
+/// Like [pre_process], but adds interpolations to an existing context, so
+/// multiple sources can share it.
+pub fn pre_process_into<'a>(source: &'a str, context: &mut JinjaContext<'a>) -> Result<String> {
     let mut blocks = Vec::new();
     let mut current_block = Vec::new();
 
@@ -64,8 +72,7 @@ pub fn pre_process(source: &str) -> Result<(String, JinjaContext)> {
     }
 
     let mut anchored_source = String::new();
-    let mut next_anchor_id = 0;
-    let mut context = JinjaContext::default();
+    let mut next_anchor_id = context.anchor_map.len();
     for block in blocks {
         match block {
             JinjaBlock::Data(data) => anchored_source += data,
@@ -88,7 +95,7 @@ pub fn pre_process(source: &str) -> Result<(String, JinjaContext)> {
         }
     }
 
-    Ok((anchored_source, context))
+    Ok(anchored_source)
 }
 
 fn find_span(source: &str, spans: Vec<Span>) -> &str {
@@ -98,22 +105,23 @@ fn find_span(source: &str, spans: Vec<Span>) -> &str {
     let mut start_index = 0;
     let mut end_index = source.len();
 
+    // lines are 1-based, columns are 0-based
     let mut line = 1;
     let mut col = 0;
-    for (index, char) in source.chars().enumerate() {
+    for (index, char) in source.char_indices() {
+        if line == start.start_line && col == start.start_col {
+            start_index = index;
+        }
+
         if char == '\n' {
             line += 1;
             col = 0;
             continue;
-        } else {
-            col += 1;
         }
+        col += 1;
 
-        if line == start.start_line && col == start.start_col {
-            start_index = index;
-        }
         if line == end.end_line && col == end.end_col {
-            end_index = index + 1;
+            end_index = index + char.len_utf8();
         }
     }
     &source[start_index..end_index]
@@ -163,7 +171,7 @@ mod test {
                 vec![
                     Span {
                         start_line: 2,
-                        start_col: 9,
+                        start_col: 8,
                         start_offset: 0,
                         end_line: 12_123_123,
                         end_col: 2_930_293,
@@ -189,7 +197,7 @@ mod test {
         let src = r###"from in_process = {{ source('salesforce', 'in_process') }}"###;
         let (pre_proc_text, ctx) = pre_process(src).unwrap();
         insta::assert_yaml_snapshot!(pre_proc_text, @"from in_process = _jinja_0");
-        insta::assert_yaml_snapshot!(ctx.anchor_map["_jinja_0"], @r#"" {{ source('salesforce', 'in_process') }}""#);
+        insta::assert_yaml_snapshot!(ctx.anchor_map["_jinja_0"], @r#""{{ source('salesforce', 'in_process') }}""#);
     }
 
     #[test]
@@ -197,7 +205,7 @@ mod test {
         let src = r###"from in_process = {{ source('salesforce', 'in_process') }}"###;
         let (pre_proc_text, ctx) = pre_process(src).unwrap();
         let post_proc_text = post_process(&pre_proc_text, ctx);
-        insta::assert_yaml_snapshot!(post_proc_text, @r#""from in_process =  {{ source('salesforce', 'in_process') }}""#);
+        insta::assert_yaml_snapshot!(post_proc_text, @r#""from in_process = {{ source('salesforce', 'in_process') }}""#);
     }
 
     #[test]
@@ -206,6 +214,6 @@ mod test {
         let (pre_proc_text, ctx) = pre_process(src).unwrap();
         insta::assert_yaml_snapshot!(ctx.header, @r#"- "{{ config(materialized = \"table\") }}""#);
         let post_proc_text = post_process(&pre_proc_text, ctx);
-        insta::assert_yaml_snapshot!(post_proc_text, @r#""{{ config(materialized = \"table\") }}\n\\nfrom in_process =  {{ source('salesforce', 'in_process') }}""#);
+        insta::assert_yaml_snapshot!(post_proc_text, @r#""{{ config(materialized = \"table\") }}\n\\nfrom in_process = {{ source('salesforce', 'in_process') }}""#);
     }
 }
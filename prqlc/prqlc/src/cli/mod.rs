@@ -25,6 +25,8 @@ use prqlc::compiler_version;
 use prqlc::debug;
 use prqlc::internal::pl_to_lineage;
 use prqlc::ir::{pl, rq};
+use prqlc::lint::Lint;
+use prqlc::parser::{load_imports, FileModuleLoader};
 use prqlc::pr;
 use prqlc::semantic;
 use prqlc::semantic::reporting::FrameCollector;
@@ -32,6 +34,8 @@ use prqlc::utils::maybe_strip_colors;
 use prqlc::{pl_to_prql, pl_to_rq_tree, prql_to_pl, prql_to_pl_tree, prql_to_tokens, rq_to_sql};
 use prqlc::{Options, SourceTree, Target};
 
+#[cfg(feature = "check-connection")]
+mod check;
 mod docs_generator;
 mod highlight;
 mod jinja;
@@ -145,18 +149,46 @@ enum Command {
         #[arg(long = "no-format", action = clap::ArgAction::SetFalse)]
         format: bool,
 
+        /// Translate expressions as written, without simplifying them
+        #[arg(long = "no-simplify", action = clap::ArgAction::SetFalse)]
+        simplify: bool,
+
+        /// Preserve Jinja expressions, so the output can be used as a dbt model
+        #[arg(long)]
+        jinja: bool,
+
         /// Target to compile to
         #[arg(short, long, default_value = "sql.any", env = "PRQLC_TARGET")]
         target: String,
 
-        /// File path into which to write the debug log to.
+        /// File path into which to write the debug log to, as JSON, HTML or
+        /// YAML by its extension. Use `-` to write YAML to stdout, before the SQL.
         #[arg(long, env = "PRQLC_DEBUG_LOG")]
         debug_log: Option<PathBuf>,
     },
 
+    /// Parse, resolve & lower into RQ, then report likely mistakes
+    #[command(name = "lint")]
+    Lint {
+        #[command(flatten)]
+        io_args: IoArgs,
+
+        /// Lints to skip; can be repeated
+        #[arg(long, value_name = "LINT")]
+        allow: Vec<Lint>,
+
+        /// Output messages as structured data, rather than as text
+        #[arg(value_enum, long)]
+        format: Option<Format>,
+    },
+
     /// Watch a directory and compile .prql files to .sql files
     Watch(watch::WatchArgs),
 
+    /// Compile & check the SQL against a database, without running it
+    #[cfg(feature = "check-connection")]
+    Check(check::CheckArgs),
+
     /// Show available compile target names
     #[command(name = "list-targets")]
     ListTargets,
@@ -281,6 +313,8 @@ impl Command {
     pub fn run(&mut self) -> Result<()> {
         match self {
             Command::Watch(command) => watch::run(command),
+            #[cfg(feature = "check-connection")]
+            Command::Check(command) => check::run(command),
             Command::ListTargets => self.list_targets(),
             // Format is handled differently to the other IO commands, since it
             // always writes to the same output.
@@ -449,6 +483,8 @@ impl Command {
             Command::Compile {
                 signature_comment,
                 format,
+                simplify,
+                jinja,
                 target,
                 debug_log,
                 ..
@@ -460,7 +496,21 @@ impl Command {
                 let opts = Options::default()
                     .with_target(Target::from_str(target).map_err(prqlc::ErrorMessages::from)?)
                     .with_signature_comment(*signature_comment)
-                    .with_format(*format);
+                    .with_format(*format)
+                    .with_simplify(*simplify);
+
+                // Jinja expressions are replaced with identifiers before
+                // compiling, and put back into the SQL afterwards
+                let originals = if *jinja {
+                    sources.sources.clone()
+                } else {
+                    HashMap::new()
+                };
+                let mut jinja_context = jinja::JinjaContext::default();
+                for (path, source) in originals.iter().sorted_by_key(|(path, _)| *path) {
+                    let anchored = jinja::pre_process_into(source, &mut jinja_context)?;
+                    sources.sources.insert(path.clone(), anchored);
+                }
 
                 let res = prql_to_pl_tree(sources)
                     .and_then(|pl| {
@@ -473,7 +523,31 @@ impl Command {
                     write_log(path)?;
                 }
 
-                res?.as_bytes().to_vec()
+                let mut sql = res?;
+                if *jinja {
+                    sql = jinja::post_process(&sql, jinja_context);
+                }
+                sql.into_bytes()
+            }
+            Command::Lint { allow, format, .. } => {
+                let lints = Lint::all()
+                    .into_iter()
+                    .filter(|l| !allow.contains(l))
+                    .collect_vec();
+
+                let rq = prql_to_pl_tree(sources)
+                    .and_then(|pl| {
+                        pl_to_rq_tree(pl, &main_path, &[semantic::NS_DEFAULT_DB.to_string()])
+                    })
+                    .map_err(|e| e.composed(sources))?;
+                let messages =
+                    prqlc::ErrorMessages::from(prqlc::lint::lint(&rq, &lints)).composed(sources);
+
+                match format {
+                    Some(Format::Json) => serde_json::to_string_pretty(&messages)?.into_bytes(),
+                    Some(Format::Yaml) => serde_yaml::to_string(&messages)?.into_bytes(),
+                    None => messages.to_string().into_bytes(),
+                }
             }
             _ => unreachable!("Other commands shouldn't reach `execute`"),
         })
@@ -490,6 +564,7 @@ impl Command {
             | Lex { io_args, .. }
             | Collect(io_args)
             | Compile { io_args, .. }
+            | Command::Lint { io_args, .. }
             | Debug(DebugCommand::Annotate(io_args) | DebugCommand::Lineage { io_args, .. }) => {
                 io_args
             }
@@ -512,7 +587,22 @@ impl Command {
             eprintln!("Enter PRQL, then press ctrl-z to compile:\n");
         }
 
-        let sources = read_files(input)?;
+        let mut sources = read_files(input)?;
+
+        // Load modules that are imported from files next to the input, unless
+        // the input is read from stdin
+        let root = input.path();
+        let dir = if root.is_dir() {
+            Some(root.to_path_buf())
+        } else {
+            root.parent()
+                .filter(|_| root.is_file())
+                .map(Path::to_path_buf)
+        };
+        if let Some(root) = dir {
+            load_imports(&mut sources, &FileModuleLoader { root })
+                .map_err(prqlc::ErrorMessages::from)?;
+        }
 
         let main_path = io_args.main_path.clone().unwrap_or_default();
 
@@ -526,6 +616,7 @@ impl Command {
             | Lex { io_args, .. }
             | Collect(io_args)
             | Compile { io_args, .. }
+            | Command::Lint { io_args, .. }
             | Debug(DebugCommand::Annotate(io_args) | DebugCommand::Lineage { io_args, .. }) => {
                 io_args.output.clone()
             }
@@ -557,6 +648,10 @@ pub fn write_log(path: &std::path::Path) -> Result<()> {
             "debug log was started, but it cannot be found after compilation"
         ));
     };
+    if path == std::path::Path::new("-") {
+        let stdout = io::stdout().lock();
+        return write_log_yaml(stdout, &debug_log);
+    }
     match path.extension().and_then(|s| s.to_str()) {
         Some("json") => {
             let file = BufWriter::new(File::create(path)?);
@@ -566,6 +661,10 @@ pub fn write_log(path: &std::path::Path) -> Result<()> {
             let file = BufWriter::new(File::create(path)?);
             debug::render_log_to_html(file, &debug_log)?;
         }
+        Some("yaml" | "yml") => {
+            let file = BufWriter::new(File::create(path)?);
+            write_log_yaml(file, &debug_log)?;
+        }
         _ => {
             return Err(anyhow!("unknown debug log format for file {path:?}"));
         }
@@ -573,6 +672,14 @@ pub fn write_log(path: &std::path::Path) -> Result<()> {
     Ok(())
 }
 
+fn write_log_yaml(writer: impl Write, debug_log: &debug::DebugLog) -> Result<()> {
+    // serde_yaml can't write nested enums, such as `NewStage(Sql(Simplify))`,
+    // so we write them as JSON would
+    let value = serde_json::to_value(debug_log)?;
+    serde_yaml::to_writer(writer, &value)?;
+    Ok(())
+}
+
 fn drop_module_def(stmts: &mut Vec<pr::Stmt>, name: &str) {
     stmts.retain(|x| x.kind.as_module_def().map_or(true, |m| m.name != name));
 }
@@ -688,6 +795,8 @@ sort full
                 io_args: IoArgs::default(),
                 signature_comment: false,
                 format: true,
+                simplify: true,
+                jinja: false,
                 target: "sql.any".to_string(),
                 debug_log: None,
             },
@@ -713,6 +822,8 @@ sort full
                 io_args: IoArgs::default(),
                 signature_comment: false,
                 format: true,
+                simplify: true,
+                jinja: false,
                 target: "sql.any".to_string(),
                 debug_log: None,
             },
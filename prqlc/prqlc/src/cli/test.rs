@@ -27,6 +27,7 @@ fn help() {
       debug             Commands for meant for debugging, prone to change
       experimental      Experimental commands are prone to change
       compile           Parse, resolve, lower into RQ & compile to SQL
+      lint              Parse, resolve & lower into RQ, then report likely mistakes
       watch             Watch a directory and compile .prql files to .sql files
       list-targets      Show available compile target names
       shell-completion  Print a shell completion for supported shells
@@ -82,10 +83,58 @@ fn compile() {
     "###);
 }
 
+#[cfg(feature = "check-connection")]
+#[test]
+fn check() {
+    let dir = TempDir::new().unwrap();
+    rusqlite::Connection::open(dir.path().join("chinook.db"))
+        .unwrap()
+        .execute_batch("CREATE TABLE albums (album_id INTEGER, name TEXT)")
+        .unwrap();
+
+    assert_cmd_snapshot!(prqlc_command()
+        .current_dir(dir.path())
+        .args(["check", "--connection", "sqlite://chinook.db"])
+        .pass_stdin("from albums | select {album_id, name}"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    The query is valid for sqlite://chinook.db
+
+    ----- stderr -----
+    ");
+
+    // the error points to the column, rather than to the comment or the string
+    assert_cmd_snapshot!(prqlc_command()
+        .current_dir(dir.path())
+        .args(["check", "--connection", "sqlite://chinook.db"])
+        .pass_stdin(r#"
+        # the title of the album
+        from albums
+        derive {label = "title"}
+        select {album_id, label, title}
+        "#), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Error:
+       ╭─[:5:34]
+       │
+     5 │         select {album_id, label, title}
+       │                                  ──┬──
+       │                                    ╰──── no such column: title
+       │
+       │ Help: the query was checked against the database, but not run
+    ───╯
+    ");
+}
+
 #[cfg(not(windows))] // Windows has slightly different output (e.g. `prqlc.exe`), so we exclude.
 #[test]
 fn compile_help() {
-    assert_cmd_snapshot!(prqlc_command().args(["compile", "--help"]), @r#"
+    assert_cmd_snapshot!(prqlc_command().args(["compile", "--help"]), @r"
     success: true
     exit_code: 0
     ----- stdout -----
@@ -112,6 +161,12 @@ fn compile_help() {
           --no-format
               Emit unformatted, dense SQL
 
+          --no-simplify
+              Translate expressions as written, without simplifying them
+
+          --jinja
+              Preserve Jinja expressions, so the output can be used as a dbt model
+
       -t, --target <TARGET>
               Target to compile to
               
@@ -119,7 +174,8 @@ fn compile_help() {
               [default: sql.any]
 
           --debug-log <DEBUG_LOG>
-              File path into which to write the debug log to
+              File path into which to write the debug log to, as JSON, HTML or YAML by its extension.
+              Use `-` to write YAML to stdout, before the SQL
               
               [env: PRQLC_DEBUG_LOG=]
 
@@ -132,6 +188,31 @@ fn compile_help() {
       -h, --help
               Print help (see a summary with '-h')
 
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn compile_jinja() {
+    assert_cmd_snapshot!(prqlc_command()
+        .args(["compile", "--hide-signature-comment", "--jinja"])
+        .pass_stdin(r#"{{ config(materialized="table") }}
+from {{ ref('tracks') }}
+filter name == "{{ var('name') }}"
+select {id, name}
+"#), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    {{ config(materialized="table") }}
+    SELECT
+      id,
+      name
+    FROM
+      {{ ref('tracks') }}
+    WHERE
+      name = '{{ var('name') }}'
+
     ----- stderr -----
     "#);
 }
@@ -325,6 +406,82 @@ fn compile_project() {
     "###);
 }
 
+#[test]
+fn debug_log_yaml() {
+    assert_cmd_snapshot!(prqlc_command()
+        .args(["compile", "--hide-signature-comment", "--debug-log=log_test.yaml"])
+        .pass_stdin("from tracks | filter true"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    SELECT
+      *
+    FROM
+      tracks
+
+    ----- stderr -----
+    ");
+
+    let log = fs::read_to_string("./log_test.yaml").unwrap();
+    let log: serde_yaml::Value = serde_yaml::from_str(&log).unwrap();
+    let entries = log["entries"].as_sequence().unwrap();
+
+    let stages = entries
+        .iter()
+        .filter_map(|entry| entry["kind"].get("NewStage"))
+        .map(|stage| serde_yaml::to_string(stage).unwrap())
+        .collect::<String>();
+    insta::assert_snapshot!(stages, @r"
+    Parsing
+    Semantic: AstExpand
+    Semantic: Resolver
+    Semantic: Lowering
+    Sql: Simplify
+    Sql: Anchor
+    Sql: Postprocess
+    Sql: Main
+    ");
+
+    // the filter is still in the RQ of lowering, and is removed by Simplify
+    let rq_after = |stage: &str| {
+        let stage = entries
+            .iter()
+            .position(|entry| {
+                let new_stage = serde_yaml::to_string(&entry["kind"]["NewStage"]).unwrap();
+                new_stage.trim_end().ends_with(stage)
+            })
+            .unwrap();
+        entries[stage..]
+            .iter()
+            .find_map(|entry| entry["kind"].get("ReprRq"))
+            .map(|rq| serde_yaml::to_string(rq).unwrap())
+            .unwrap()
+    };
+    assert!(rq_after("Lowering").contains("Filter"));
+    assert!(!rq_after("Simplify").contains("Filter"));
+}
+
+#[test]
+fn debug_log_stdout() {
+    let dir = TempDir::new().unwrap();
+    let input = dir.path().join("tracks.prql");
+    fs::write(&input, "from tracks").unwrap();
+
+    let output = prqlc_command()
+        .args(["compile", "--hide-signature-comment", "--debug-log=-"])
+        .arg(input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    // the log is written as YAML, followed by the SQL
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let (log, sql) = stdout.rsplit_once("SELECT").unwrap();
+    let log: serde_yaml::Value = serde_yaml::from_str(log).unwrap();
+    assert!(log["entries"].as_sequence().is_some_and(|e| !e.is_empty()));
+    assert_eq!(sql, "\n  *\nFROM\n  tracks\n");
+}
+
 #[test]
 fn format() {
     // Test stdin formatting
@@ -394,7 +551,7 @@ fn compare_directories(dir1: &Path, dir2: &Path) {
 fn debug() {
     assert_cmd_snapshot!(prqlc_command()
         .args(["debug", "lineage"])
-        .pass_stdin("from tracks | select {artist, album}"), @r###"
+        .pass_stdin("from tracks | select {artist, album}"), @r"
     success: true
     exit_code: 0
     ----- stdout -----
@@ -405,29 +562,29 @@ fn debug() {
           name:
           - tracks
           - artist
-          target_id: 120
+          target_id: 148
           target_name: null
         - !Single
           name:
           - tracks
           - album
-          target_id: 121
+          target_id: 149
           target_name: null
         inputs:
-        - id: 118
+        - id: 146
           name: tracks
           table:
           - default_db
           - tracks
     nodes:
-    - id: 118
+    - id: 146
       kind: Ident
       span: 1:0-11
       ident: !Ident
       - default_db
       - tracks
-      parent: 123
-    - id: 120
+      parent: 151
+    - id: 148
       kind: Ident
       span: 1:22-28
       ident: !Ident
@@ -435,9 +592,9 @@ fn debug() {
       - tracks
       - artist
       targets:
-      - 118
-      parent: 122
-    - id: 121
+      - 146
+      parent: 150
+    - id: 149
       kind: Ident
       span: 1:30-35
       ident: !Ident
@@ -445,21 +602,21 @@ fn debug() {
       - tracks
       - album
       targets:
-      - 118
-      parent: 122
-    - id: 122
+      - 146
+      parent: 150
+    - id: 150
       kind: Tuple
       span: 1:21-36
       children:
-      - 120
-      - 121
-      parent: 123
-    - id: 123
+      - 148
+      - 149
+      parent: 151
+    - id: 151
       kind: 'TransformCall: Select'
       span: 1:14-36
       children:
-      - 118
-      - 122
+      - 146
+      - 150
     ast:
       name: Project
       stmts:
@@ -493,7 +650,7 @@ fn debug() {
         span: 1:0-36
 
     ----- stderr -----
-    "###);
+    ");
 
     // Don't test the output of this, since on one min-versions check it had
     // different results, and didn't repro on Mac. It having different results
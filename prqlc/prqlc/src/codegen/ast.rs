@@ -185,10 +185,17 @@ impl WriteSource for pr::ExprKind {
                     let arg = write_within(arg, self, opt.clone())?;
                     r += opt.consume(&arg)?;
                 }
+                // With more than one argument, `join x (a == b)` is clearer than
+                // `join x a == b`, even though both parse the same.
+                let has_many_args = func_call.args.len() + func_call.named_args.len() > 1;
                 for arg in &func_call.args {
                     r += opt.consume(" ")?;
 
-                    let arg = write_within(arg, self, opt.clone())?;
+                    let mut arg_opt = opt.clone();
+                    if has_many_args && matches!(arg.kind, pr::ExprKind::Binary(_)) {
+                        arg_opt.context_strength = binding_strength(&arg.kind) + 1;
+                    }
+                    let arg = write_within(arg, self, arg_opt)?;
                     r += opt.consume(&arg)?;
                 }
                 Some(r)
@@ -392,13 +399,25 @@ impl WriteSource for Vec<pr::Stmt> {
         opt.reset_line()?;
 
         let mut r = String::new();
+        let mut prev_is_main = false;
         for stmt in self {
+            let is_main = stmt
+                .kind
+                .as_var_def()
+                .map_or(false, |x| x.kind == pr::VarDefKind::Main);
+
             if !r.is_empty() {
+                // without a `;`, the line break would continue the previous pipeline
+                if prev_is_main && is_main {
+                    r.truncate(r.trim_end().len());
+                    r += ";\n";
+                }
                 r += "\n";
             }
 
             r += &opt.write_indent();
             r += &stmt.write_or_expand(opt.clone());
+            prev_is_main = is_main;
         }
         Some(r)
     }
@@ -408,6 +427,32 @@ impl WriteSource for pr::Stmt {
     fn write(&self, mut opt: WriteOpt) -> Option<String> {
         let mut r = String::new();
 
+        // Comments are written before the statement, except for those within a
+        // main pipeline, which are written between its transforms.
+        let mut comments = self.comments.iter().peekable();
+        let body_start = match &self.kind {
+            pr::StmtKind::VarDef(var_def)
+                if matches!(var_def.kind, pr::VarDefKind::Into | pr::VarDefKind::Main) =>
+            {
+                let val = var_def.value.as_ref().unwrap();
+                match &val.kind {
+                    pr::ExprKind::Pipeline(pipeline) => pipeline.exprs.first(),
+                    _ => Some(val.as_ref()),
+                }
+                .and_then(|e| e.span)
+                .map(|s| s.start)
+            }
+            _ => None,
+        };
+        while let Some(comment) = comments
+            .next_if(|c| !c.trailing && body_start.map_or(true, |start| c.span.start < start))
+        {
+            r += &format!("#{}\n", comment.text);
+            r += &opt.write_indent();
+        }
+
+        let indent = opt.write_indent();
+
         for annotation in &self.annotations {
             r += "@";
             r += &annotation.expr.write(opt.clone())?;
@@ -454,8 +499,25 @@ impl WriteSource for pr::Stmt {
                     let val = var_def.value.as_ref().unwrap();
                     match &val.kind {
                         pr::ExprKind::Pipeline(pipeline) => {
-                            for expr in &pipeline.exprs {
+                            for (i, expr) in pipeline.exprs.iter().enumerate() {
+                                if let Some(start) = expr.span.map(|s| s.start) {
+                                    while let Some(comment) =
+                                        comments.next_if(|c| c.span.start < start)
+                                    {
+                                        r += &format!("#{}\n", comment.text);
+                                    }
+                                }
+
                                 r += &expr.write(opt.clone())?;
+
+                                let next_start = (pipeline.exprs.get(i + 1))
+                                    .and_then(|e| e.span)
+                                    .map(|s| s.start);
+                                while let Some(comment) = comments.next_if(|c| {
+                                    c.trailing && next_start.map_or(true, |n| c.span.start < n)
+                                }) {
+                                    r += &format!(" #{}", comment.text);
+                                }
                                 r += "\n";
                             }
                         }
@@ -500,6 +562,18 @@ impl WriteSource for pr::Stmt {
                 r += "\n";
             }
         }
+
+        // remaining trailing comments go at the end of the last line, others
+        // after the statement
+        for comment in comments {
+            if comment.trailing {
+                r.truncate(r.trim_end().len());
+                r += &format!(" #{}\n", comment.text);
+            } else {
+                r += &indent;
+                r += &format!("#{}\n", comment.text);
+            }
+        }
         Some(r)
     }
 }
@@ -740,6 +814,26 @@ into a
         assert_is_formatted(
             r#"
 prql version:"^0.9" target:sql.sqlite
+"#,
+        );
+    }
+
+    #[test]
+    fn test_comments() {
+        assert_is_formatted(
+            r#"
+# leading
+from employees
+# before filter
+filter salary > 100 # trailing
+take 10
+"#,
+        );
+
+        assert_is_formatted(
+            r#"
+# a constant
+let a = 5 # five
 "#,
         );
     }
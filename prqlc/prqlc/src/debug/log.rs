@@ -144,6 +144,7 @@ pub enum StageSemantic {
 
 #[derive(Clone, Copy, Serialize, AsRefStr)]
 pub enum StageSql {
+    Simplify,
     Anchor,
     Postprocess,
     Main,
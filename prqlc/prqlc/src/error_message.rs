@@ -12,7 +12,7 @@ use crate::{Error, Errors, MessageKind, SourceTree};
 
 #[derive(Clone, Serialize)]
 pub struct ErrorMessage {
-    /// Message kind. Currently only Error is implemented.
+    /// Message kind. Lints are reported with [MessageKind::Lint].
     pub kind: MessageKind,
     /// Machine-readable identifier of the error
     pub code: Option<String>,
@@ -54,7 +54,12 @@ impl Display for ErrorMessage {
                 .map(|c| format!("[{c}] "))
                 .unwrap_or_default();
 
-            writeln!(f, "{}Error: {}", code, &self.reason)?;
+            let kind = match self.kind {
+                MessageKind::Error => "Error",
+                MessageKind::Warning => "Warning",
+                MessageKind::Lint => "Lint",
+            };
+            writeln!(f, "{}{}: {}", code, kind, &self.reason)?;
             for hint in &self.hints {
                 // TODO: consider alternative formatting for hints.
                 writeln!(f, "↳ Hint: {}", hint)?;
@@ -85,6 +90,14 @@ impl From<Error> for ErrorMessage {
     }
 }
 
+impl From<Vec<Error>> for ErrorMessages {
+    fn from(errors: Vec<Error>) -> Self {
+        ErrorMessages {
+            inner: errors.into_iter().map(ErrorMessage::from).collect(),
+        }
+    }
+}
+
 impl From<Vec<ErrorMessage>> for ErrorMessages {
     fn from(errors: Vec<ErrorMessage>) -> Self {
         ErrorMessages { inner: errors }
@@ -169,7 +182,13 @@ impl ErrorMessage {
 
         let span = Range::from(self.span?);
 
-        let mut report = Report::build(ReportKind::Error, source_path.clone(), span.start)
+        let kind = match self.kind {
+            MessageKind::Error => ReportKind::Error,
+            MessageKind::Warning => ReportKind::Warning,
+            MessageKind::Lint => ReportKind::Custom("Lint", ariadne::Color::Cyan),
+        };
+
+        let mut report = Report::build(kind, source_path.clone(), span.start)
             .with_config(config)
             .with_label(Label::new((source_path, span)).with_message(&self.reason));
 
@@ -64,9 +64,17 @@ pub enum TransformKind {
     Select {
         assigns: Box<Expr>,
     },
+    Rename {
+        assigns: Box<Expr>,
+    },
     Filter {
         filter: Box<Expr>,
     },
+    /// Fails the query if the condition, an aggregation of the relation,
+    /// does not hold. Otherwise, the relation is passed through unchanged.
+    Assert {
+        condition: Box<Expr>,
+    },
     Aggregate {
         assigns: Box<Expr>,
     },
@@ -75,6 +83,8 @@ pub enum TransformKind {
     },
     Take {
         range: Range,
+        /// Set by `take first` and `take last`, which require a sorted relation.
+        sorted: bool,
     },
     Join {
         side: JoinSide,
@@ -113,6 +123,11 @@ pub enum JoinSide {
     Left,
     Right,
     Full,
+    /// Rows of the left relation that have a match, without the columns of
+    /// the right relation.
+    Semi,
+    /// Rows of the left relation that have no match.
+    Anti,
 }
 
 impl Expr {
@@ -239,17 +239,24 @@ pub fn fold_transform_kind<T: ?Sized + PlFold>(
         Select { assigns } => Select {
             assigns: Box::new(fold.fold_expr(*assigns)?),
         },
+        Rename { assigns } => Rename {
+            assigns: Box::new(fold.fold_expr(*assigns)?),
+        },
         Filter { filter } => Filter {
             filter: Box::new(fold.fold_expr(*filter)?),
         },
+        Assert { condition } => Assert {
+            condition: Box::new(fold.fold_expr(*condition)?),
+        },
         Aggregate { assigns } => Aggregate {
             assigns: Box::new(fold.fold_expr(*assigns)?),
         },
         Sort { by } => Sort {
             by: fold_column_sorts(fold, by)?,
         },
-        Take { range } => Take {
+        Take { range, sorted } => Take {
             range: fold_range(fold, range)?,
+            sorted,
         },
         Join { side, with, filter } => Join {
             side,
@@ -18,6 +18,7 @@ pub use self::fold::*;
 pub use self::lineage::*;
 pub use self::stmt::*;
 pub use self::utils::*;
+pub use self::visit::*;
 
 mod expr;
 mod extra;
@@ -25,6 +26,7 @@ mod fold;
 mod lineage;
 mod stmt;
 mod utils;
+mod visit;
 
 pub fn print_mem_sizes() {
     use std::mem::size_of;
@@ -0,0 +1,209 @@
+/// A trait to "visit" a PL AST without rebuilding it. It is the read-only
+/// counterpart of [PlFold](super::PlFold): all nodes are taken by reference
+/// and nothing is returned, so analyses don't need to clone the tree.
+use super::*;
+
+// As with PlFold, the default impls call functions defined outside the trait,
+// so implementors can override a method and still call the default for the
+// cases they don't handle.
+pub trait PlVisitor {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        visit_stmt_kind(self, &stmt.kind)
+    }
+    fn visit_stmts(&mut self, stmts: &[Stmt]) {
+        for stmt in stmts {
+            self.visit_stmt(stmt);
+        }
+    }
+    fn visit_expr(&mut self, expr: &Expr) {
+        self.visit_expr_kind(&expr.kind)
+    }
+    fn visit_expr_kind(&mut self, expr_kind: &ExprKind) {
+        visit_expr_kind(self, expr_kind)
+    }
+    fn visit_exprs(&mut self, exprs: &[Expr]) {
+        for expr in exprs {
+            self.visit_expr(expr);
+        }
+    }
+    fn visit_var_def(&mut self, var_def: &VarDef) {
+        if let Some(value) = &var_def.value {
+            self.visit_expr(value);
+        }
+    }
+    fn visit_module_def(&mut self, module_def: &ModuleDef) {
+        self.visit_stmts(&module_def.stmts)
+    }
+    fn visit_func_call(&mut self, func_call: &FuncCall) {
+        visit_func_call(self, func_call)
+    }
+    fn visit_transform_call(&mut self, transform_call: &TransformCall) {
+        visit_transform_call(self, transform_call)
+    }
+    fn visit_transform_kind(&mut self, transform_kind: &TransformKind) {
+        visit_transform_kind(self, transform_kind)
+    }
+    fn visit_func(&mut self, func: &Func) {
+        visit_func(self, func)
+    }
+    fn visit_interpolate_item(&mut self, item: &InterpolateItem) {
+        if let InterpolateItem::Expr { expr, .. } = item {
+            self.visit_expr(expr);
+        }
+    }
+    fn visit_window(&mut self, window: &WindowFrame) {
+        visit_range(self, &window.range)
+    }
+}
+
+pub fn visit_stmt_kind<V: ?Sized + PlVisitor>(visitor: &mut V, stmt_kind: &StmtKind) {
+    match stmt_kind {
+        StmtKind::VarDef(var_def) => visitor.visit_var_def(var_def),
+        StmtKind::ModuleDef(module_def) => visitor.visit_module_def(module_def),
+        StmtKind::QueryDef(_) | StmtKind::TypeDef(_) | StmtKind::ImportDef(_) => {}
+    }
+}
+
+pub fn visit_expr_kind<V: ?Sized + PlVisitor>(visitor: &mut V, expr_kind: &ExprKind) {
+    use ExprKind::*;
+    match expr_kind {
+        All { within, except } => {
+            visitor.visit_expr(within);
+            visitor.visit_expr(except);
+        }
+        Tuple(items) | Array(items) => visitor.visit_exprs(items),
+        SString(items) | FString(items) => {
+            for item in items {
+                visitor.visit_interpolate_item(item);
+            }
+        }
+        Case(cases) => {
+            for case in cases {
+                visitor.visit_expr(&case.condition);
+                visitor.visit_expr(&case.value);
+            }
+        }
+
+        FuncCall(func_call) => visitor.visit_func_call(func_call),
+        Func(func) => visitor.visit_func(func),
+
+        TransformCall(transform) => visitor.visit_transform_call(transform),
+        RqOperator { args, .. } => visitor.visit_exprs(args),
+
+        Ident(_) | Param(_) | Internal(_) | Literal(_) => {}
+    }
+}
+
+pub fn visit_range<V: ?Sized + PlVisitor>(visitor: &mut V, range: &Range) {
+    for bound in range.start.iter().chain(&range.end) {
+        visitor.visit_expr(bound);
+    }
+}
+
+pub fn visit_column_sorts<V: ?Sized + PlVisitor>(visitor: &mut V, sorts: &[ColumnSort]) {
+    for sort in sorts {
+        visitor.visit_expr(&sort.column);
+    }
+}
+
+pub fn visit_func_call<V: ?Sized + PlVisitor>(visitor: &mut V, func_call: &FuncCall) {
+    visitor.visit_expr(&func_call.name);
+    visitor.visit_exprs(&func_call.args);
+    for expr in func_call.named_args.values() {
+        visitor.visit_expr(expr);
+    }
+}
+
+pub fn visit_transform_call<V: ?Sized + PlVisitor>(visitor: &mut V, t: &TransformCall) {
+    visitor.visit_transform_kind(&t.kind);
+    visitor.visit_expr(&t.input);
+    if let Some(partition) = &t.partition {
+        visitor.visit_expr(partition);
+    }
+    visitor.visit_window(&t.frame);
+    visit_column_sorts(visitor, &t.sort);
+}
+
+pub fn visit_transform_kind<V: ?Sized + PlVisitor>(visitor: &mut V, t: &TransformKind) {
+    use TransformKind::*;
+    match t {
+        Derive { assigns } | Select { assigns } | Rename { assigns } | Aggregate { assigns } => {
+            visitor.visit_expr(assigns)
+        }
+        Filter { filter } => visitor.visit_expr(filter),
+        Assert { condition } => visitor.visit_expr(condition),
+        Sort { by } => visit_column_sorts(visitor, by),
+        Take { range, .. } => visit_range(visitor, range),
+        Join { with, filter, .. } => {
+            visitor.visit_expr(with);
+            visitor.visit_expr(filter);
+        }
+        Append(bottom) => visitor.visit_expr(bottom),
+        Group { by, pipeline } => {
+            visitor.visit_expr(by);
+            visitor.visit_expr(pipeline);
+        }
+        Window {
+            range, pipeline, ..
+        } => {
+            visit_range(visitor, range);
+            visitor.visit_expr(pipeline);
+        }
+        Loop(pipeline) => visitor.visit_expr(pipeline),
+    }
+}
+
+pub fn visit_func<V: ?Sized + PlVisitor>(visitor: &mut V, func: &Func) {
+    visitor.visit_expr(&func.body);
+    visitor.visit_exprs(&func.args);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct IdentCounter {
+        idents: usize,
+        calls: usize,
+    }
+
+    impl PlVisitor for IdentCounter {
+        fn visit_expr_kind(&mut self, expr_kind: &ExprKind) {
+            if let ExprKind::Ident(_) = expr_kind {
+                self.idents += 1;
+            }
+            visit_expr_kind(self, expr_kind)
+        }
+
+        fn visit_func_call(&mut self, func_call: &FuncCall) {
+            self.calls += 1;
+            visit_func_call(self, func_call)
+        }
+    }
+
+    fn ident(name: &str) -> Expr {
+        Expr::new(Ident::from_name(name))
+    }
+
+    #[test]
+    fn test_count_idents() {
+        // average (salary + bonus) [tax]
+        let call = FuncCall::new_simple(
+            ident("average"),
+            vec![
+                Expr::new(ExprKind::RqOperator {
+                    name: "std.add".to_string(),
+                    args: vec![ident("salary"), ident("bonus")],
+                }),
+                Expr::new(ExprKind::Tuple(vec![ident("tax")])),
+            ],
+        );
+
+        let mut counter = IdentCounter::default();
+        counter.visit_expr(&Expr::new(ExprKind::FuncCall(call)));
+
+        assert_eq!(counter.idents, 4);
+        assert_eq!(counter.calls, 1);
+    }
+}
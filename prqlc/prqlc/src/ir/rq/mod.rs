@@ -13,6 +13,7 @@ pub use ids::*;
 use prqlc_parser::lexer::lr;
 pub use transform::*;
 pub use utils::*;
+pub use visit::*;
 
 use super::pl::QueryDef;
 use super::pl::TableExternRef;
@@ -22,6 +23,7 @@ mod fold;
 mod ids;
 mod transform;
 mod utils;
+mod visit;
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RelationalQuery {
@@ -0,0 +1,194 @@
+/// A trait to "visit" a PRQL AST without rebuilding it. It is the read-only
+/// counterpart of [RqFold](super::RqFold): all nodes are taken by reference
+/// and nothing is returned, so analyses don't need to clone the tree.
+use super::*;
+use crate::ir::generic::ColumnSort;
+
+// As with RqFold, the default impls call functions defined outside the trait,
+// so implementors can override a method and still call the default for the
+// cases they don't handle.
+pub trait RqVisitor {
+    fn visit_transform(&mut self, transform: &Transform) {
+        visit_transform(self, transform)
+    }
+    fn visit_transforms(&mut self, transforms: &[Transform]) {
+        for transform in transforms {
+            self.visit_transform(transform);
+        }
+    }
+    fn visit_table(&mut self, table: &TableDecl) {
+        self.visit_relation(&table.relation)
+    }
+    fn visit_relation(&mut self, relation: &Relation) {
+        visit_relation_kind(self, &relation.kind)
+    }
+    fn visit_table_ref(&mut self, table_ref: &TableRef) {
+        for (col, cid) in &table_ref.columns {
+            self.visit_relation_column(col);
+            self.visit_cid(*cid);
+        }
+    }
+    fn visit_query(&mut self, query: &RelationalQuery) {
+        for table in &query.tables {
+            self.visit_table(table);
+        }
+        self.visit_relation(&query.relation)
+    }
+    fn visit_expr(&mut self, expr: &Expr) {
+        visit_expr_kind(self, &expr.kind)
+    }
+    fn visit_relation_column(&mut self, _col: &RelationColumn) {}
+    fn visit_cid(&mut self, _cid: CId) {}
+    fn visit_compute(&mut self, compute: &Compute) {
+        visit_compute(self, compute)
+    }
+}
+
+pub fn visit_compute<V: ?Sized + RqVisitor>(visitor: &mut V, compute: &Compute) {
+    visitor.visit_cid(compute.id);
+    visitor.visit_expr(&compute.expr);
+
+    if let Some(window) = &compute.window {
+        let range = &window.frame.range;
+        for bound in range.start.iter().chain(&range.end) {
+            visitor.visit_expr(bound);
+        }
+        for cid in &window.partition {
+            visitor.visit_cid(*cid);
+        }
+        visit_column_sorts(visitor, &window.sort);
+    }
+}
+
+pub fn visit_relation_kind<V: ?Sized + RqVisitor>(visitor: &mut V, rel: &RelationKind) {
+    match rel {
+        RelationKind::ExternRef(_) | RelationKind::Literal(_) => {}
+        RelationKind::Pipeline(transforms) => visitor.visit_transforms(transforms),
+        RelationKind::SString(items) => visit_interpolate_items(visitor, items),
+        RelationKind::BuiltInFunction { args, .. } => {
+            for arg in args {
+                visitor.visit_expr(arg);
+            }
+        }
+    }
+}
+
+pub fn visit_transform<V: ?Sized + RqVisitor>(visitor: &mut V, transform: &Transform) {
+    use Transform::*;
+
+    match transform {
+        From(table_ref) | Append(table_ref) => visitor.visit_table_ref(table_ref),
+        Compute(compute) => visitor.visit_compute(compute),
+        Aggregate { partition, compute } => {
+            for cid in partition.iter().chain(compute) {
+                visitor.visit_cid(*cid);
+            }
+        }
+        Select(cids) => {
+            for cid in cids {
+                visitor.visit_cid(*cid);
+            }
+        }
+        Filter(expr) => visitor.visit_expr(expr),
+        Sort(sorts) => visit_column_sorts(visitor, sorts),
+        Take(take) => {
+            for cid in &take.partition {
+                visitor.visit_cid(*cid);
+            }
+            visit_column_sorts(visitor, &take.sort);
+        }
+        Join { with, filter, .. } => {
+            visitor.visit_table_ref(with);
+            visitor.visit_expr(filter);
+        }
+        Loop(transforms) => visitor.visit_transforms(transforms),
+    }
+}
+
+pub fn visit_column_sorts<V: ?Sized + RqVisitor>(visitor: &mut V, sorts: &[ColumnSort<CId>]) {
+    for sort in sorts {
+        visitor.visit_cid(sort.column);
+    }
+}
+
+pub fn visit_expr_kind<V: ?Sized + RqVisitor>(visitor: &mut V, kind: &ExprKind) {
+    match kind {
+        ExprKind::ColumnRef(cid) => visitor.visit_cid(*cid),
+        ExprKind::SString(items) => visit_interpolate_items(visitor, items),
+        ExprKind::Case(cases) => {
+            for case in cases {
+                visitor.visit_expr(&case.condition);
+                visitor.visit_expr(&case.value);
+            }
+        }
+        ExprKind::Operator { args, .. } | ExprKind::Array(args) => {
+            for arg in args {
+                visitor.visit_expr(arg);
+            }
+        }
+        ExprKind::Param(_) | ExprKind::Literal(_) => {}
+    }
+}
+
+pub fn visit_interpolate_items<V: ?Sized + RqVisitor>(visitor: &mut V, items: &[InterpolateItem]) {
+    for item in items {
+        if let InterpolateItem::Expr { expr, .. } = item {
+            visitor.visit_expr(expr);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::pl::Literal;
+
+    #[derive(Default)]
+    struct NodeCounter {
+        exprs: usize,
+        cids: usize,
+    }
+
+    impl RqVisitor for NodeCounter {
+        fn visit_expr(&mut self, expr: &Expr) {
+            self.exprs += 1;
+            visit_expr_kind(self, &expr.kind)
+        }
+
+        fn visit_cid(&mut self, _cid: CId) {
+            self.cids += 1;
+        }
+    }
+
+    fn expr(kind: ExprKind) -> Expr {
+        Expr { kind, span: None }
+    }
+
+    #[test]
+    fn test_count_nodes() {
+        // filter (col_1 + 1) > col_2 | select {col_1, col_2}
+        let filter = expr(ExprKind::Operator {
+            name: "std.gt".to_string(),
+            args: vec![
+                expr(ExprKind::Operator {
+                    name: "std.add".to_string(),
+                    args: vec![
+                        expr(ExprKind::ColumnRef(CId::from(1))),
+                        expr(ExprKind::Literal(Literal::Integer(1))),
+                    ],
+                }),
+                expr(ExprKind::ColumnRef(CId::from(2))),
+            ],
+        });
+        let transforms = vec![
+            Transform::Filter(filter),
+            Transform::Select(vec![CId::from(1), CId::from(2)]),
+        ];
+
+        let mut counter = NodeCounter::default();
+        counter.visit_transforms(&transforms);
+
+        assert_eq!(counter.exprs, 5);
+        assert_eq!(counter.cids, 4);
+    }
+}
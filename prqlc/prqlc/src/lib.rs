@@ -103,19 +103,24 @@ use semver::Version;
 use serde::{Deserialize, Serialize};
 use strum::VariantNames;
 
+pub use cache::CompilerCache;
 pub use error_message::{ErrorMessage, ErrorMessages, SourceLocation};
 pub use prqlc_parser::error::{Error, ErrorSource, Errors, MessageKind, Reason, WithErrorInfo};
 pub use prqlc_parser::lexer::lr;
 pub use prqlc_parser::parser::pr;
 pub use prqlc_parser::span::Span;
 
+mod cache;
 mod codegen;
 pub mod debug;
 mod error_message;
 pub mod ir;
+pub mod lint;
 pub mod parser;
 pub mod semantic;
 pub mod sql;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod testing;
 #[cfg(feature = "cli")]
 pub mod utils;
 #[cfg(not(feature = "cli"))]
@@ -201,28 +206,121 @@ pub fn compile(prql: &str, options: &Options) -> Result<String, ErrorMessages> {
     Ok(&sources)
         .and_then(parser::parse)
         .and_then(|ast| {
-            semantic::resolve_and_lower(ast, &[], None)
+            parser::split_queries(ast)
+                .into_iter()
+                .map(|ast| semantic::resolve_and_lower(ast, &[], None))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| e.with_source(ErrorSource::NameResolver).into())
+        })
+        .and_then(|rqs| {
+            sql::compile_many(rqs, options).map_err(|e| e.with_source(ErrorSource::SQL).into())
+        })
+        .map_err(|e| compose_errors(e, &sources, options))
+}
+
+/// Compile a PRQL string into SQL, replacing query parameters with numbered
+/// placeholders, so the values can be bound by the database driver.
+///
+/// Returns the SQL and the names of the parameters, in the order of their
+/// numbers. A parameter used multiple times is numbered once.
+///
+/// ```
+/// use prqlc::{compile_with_params, Options};
+///
+/// let opts = Options::default().no_signature().no_format();
+/// let prql = "from orders | filter customer_id == $customer_id";
+/// let (sql, params) = compile_with_params(prql, &opts).unwrap();
+/// assert_eq!("SELECT * FROM orders WHERE customer_id = $1", sql);
+/// assert_eq!(params, vec!["customer_id"]);
+/// ```
+pub fn compile_with_params(
+    prql: &str,
+    options: &Options,
+) -> Result<(String, Vec<String>), ErrorMessages> {
+    let sources = SourceTree::from(prql);
+
+    Ok(&sources)
+        .and_then(parser::parse)
+        .and_then(|ast| {
+            parser::split_queries(ast)
+                .into_iter()
+                .map(|ast| semantic::resolve_and_lower(ast, &[], None))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| e.with_source(ErrorSource::NameResolver).into())
+        })
+        .and_then(|rqs| {
+            sql::compile_with_params(rqs, options)
+                .map_err(|e| e.with_source(ErrorSource::SQL).into())
+        })
+        .map_err(|e| compose_errors(e, &sources, options))
+}
+
+/// Compile a PRQL expression into the SQL of just that expression, without
+/// a surrounding query; for example to embed a condition into existing SQL.
+///
+/// Columns are referenced by name only, without a relation.
+///
+/// ```
+/// use prqlc::{compile_fragment, Options};
+///
+/// let sql = compile_fragment("age > 18 && active", &Options::default()).unwrap();
+/// assert_eq!("age > 18 AND active", sql)
+/// ```
+pub fn compile_fragment(prql: &str, options: &Options) -> Result<String, ErrorMessages> {
+    let sources = SourceTree::from(prql);
+
+    Ok(&sources)
+        .and_then(parser::parse)
+        .and_then(|ast| {
+            parser::wrap_fragment(ast)
+                .and_then(|ast| semantic::resolve_and_lower(ast, &[], None))
                 .map_err(|e| e.with_source(ErrorSource::NameResolver).into())
         })
         .and_then(|rq| {
-            sql::compile(rq, options).map_err(|e| e.with_source(ErrorSource::SQL).into())
+            sql::compile_fragment(rq, options).map_err(|e| e.with_source(ErrorSource::SQL).into())
         })
-        .map_err(|e| {
-            let error_messages = ErrorMessages::from(e).composed(&sources);
-            match options.display {
-                DisplayOptions::AnsiColor => error_messages,
-                DisplayOptions::Plain => ErrorMessages {
-                    inner: error_messages
-                        .inner
-                        .into_iter()
-                        .map(|e| ErrorMessage {
-                            display: e.display.map(|s| strip_str(&s).to_string()),
-                            ..e
-                        })
-                        .collect(),
-                },
-            }
+        .map_err(|e| compose_errors(e, &sources, options))
+}
+
+/// Check a query for likely mistakes which don't prevent it from compiling.
+///
+/// Returns a message of kind [MessageKind::Lint] for each finding of the
+/// given lints, or errors if the query doesn't compile.
+///
+/// ```
+/// use prqlc::lint::Lint;
+///
+/// let lints = prqlc::lint("from employees | take 10", &Lint::all()).unwrap();
+/// assert_eq!(lints.inner[0].code.as_deref(), Some("L002"));
+/// ```
+pub fn lint(prql: &str, lints: &[lint::Lint]) -> Result<ErrorMessages, ErrorMessages> {
+    let sources = SourceTree::from(prql);
+
+    Ok(&sources)
+        .and_then(parser::parse)
+        .and_then(|ast| {
+            semantic::resolve_and_lower(ast, &[], None)
+                .map_err(|e| e.with_source(ErrorSource::NameResolver).into())
         })
+        .map(|rq| ErrorMessages::from(lint::lint(&rq, lints)).composed(&sources))
+        .map_err(|e| ErrorMessages::from(e).composed(&sources))
+}
+
+fn compose_errors(errors: Errors, sources: &SourceTree, options: &Options) -> ErrorMessages {
+    let error_messages = ErrorMessages::from(errors).composed(sources);
+    match options.display {
+        DisplayOptions::AnsiColor => error_messages,
+        DisplayOptions::Plain => ErrorMessages {
+            inner: error_messages
+                .inner
+                .into_iter()
+                .map(|e| ErrorMessage {
+                    display: e.display.map(|s| strip_str(&s).to_string()),
+                    ..e
+                })
+                .collect(),
+        },
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -301,6 +399,38 @@ pub struct Options {
     /// - Strip colors from the output (possibly also with a library such as
     ///   `anstream`).
     pub display: DisplayOptions,
+
+    /// Values of query parameters, such as `$customer_id`, keyed by name
+    /// without the `$`. They are written into the SQL as literals, escaped
+    /// for the dialect. Parameters without a value are left as placeholders.
+    ///
+    /// Defaults to no values.
+    #[serde(default)]
+    pub params: HashMap<String, lr::Literal>,
+
+    /// Which identifiers are quoted in the generated SQL.
+    ///
+    /// Defaults to quoting only identifiers that need it.
+    #[serde(default)]
+    pub ident_quoting: sql::IdentQuoting,
+
+    /// How the case of identifiers is changed in the generated SQL.
+    ///
+    /// Defaults to preserving the case.
+    #[serde(default)]
+    pub ident_case: sql::IdentCase,
+
+    /// Simplify expressions before generating SQL: fold constant integer
+    /// arithmetic, remove double negations and `filter true`, and merge
+    /// adjacent filters. Disable it to translate expressions as written.
+    ///
+    /// Defaults to true.
+    #[serde(default = "default_simplify")]
+    pub simplify: bool,
+}
+
+fn default_simplify() -> bool {
+    true
 }
 
 impl Default for Options {
@@ -311,6 +441,10 @@ impl Default for Options {
             signature_comment: true,
             color: true,
             display: DisplayOptions::AnsiColor,
+            params: HashMap::new(),
+            ident_quoting: sql::IdentQuoting::default(),
+            ident_case: sql::IdentCase::default(),
+            simplify: true,
         }
     }
 }
@@ -349,6 +483,30 @@ impl Options {
         self.display = display;
         self
     }
+
+    pub fn with_param(mut self, name: &str, value: lr::Literal) -> Self {
+        self.params.insert(name.to_string(), value);
+        self
+    }
+
+    pub fn with_ident_quoting(mut self, ident_quoting: sql::IdentQuoting) -> Self {
+        self.ident_quoting = ident_quoting;
+        self
+    }
+
+    pub fn with_ident_case(mut self, ident_case: sql::IdentCase) -> Self {
+        self.ident_case = ident_case;
+        self
+    }
+
+    pub fn with_simplify(mut self, simplify: bool) -> Self {
+        self.simplify = simplify;
+        self
+    }
+
+    pub fn no_simplify(self) -> Self {
+        self.with_simplify(false)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, strum::EnumString)]
@@ -414,6 +572,31 @@ pub fn pl_to_prql(pl: &pr::ModuleDef) -> Result<String, ErrorMessages> {
     Ok(codegen::WriteSource::write(&pl.stmts, codegen::WriteOpt::default()).unwrap())
 }
 
+/// Translate a SQL query into PRQL, to help migrating existing queries.
+///
+/// Only a subset of SELECT is supported; parts of expressions that have no
+/// PRQL counterpart are kept as s-strings.
+///
+/// ```
+/// use prqlc::{sql::Dialect, sql_to_prql};
+///
+/// let sql = "SELECT name, age FROM employees WHERE age > 30 ORDER BY age DESC LIMIT 10";
+/// let prql = sql_to_prql(sql, Dialect::Generic).unwrap();
+///
+/// assert_eq!(
+///     prql,
+///     "from employees\nfilter age > 30\nselect {name, age}\nsort {-age}\ntake 10\n"
+/// );
+/// ```
+pub fn sql_to_prql(sql: &str, dialect: sql::Dialect) -> Result<String, ErrorMessages> {
+    let stmts = sql::sql_to_pr(sql, dialect).map_err(|e| e.with_source(ErrorSource::SQL))?;
+
+    pl_to_prql(&pr::ModuleDef {
+        name: "main".to_string(),
+        stmts,
+    })
+}
+
 /// JSON serialization and deserialization functions
 pub mod json {
     use super::*;
@@ -438,11 +621,105 @@ pub mod json {
         serde_json::from_str(json).map_err(convert_json_err)
     }
 
+    /// Version of the schema written by the `*_versioned` functions. It is
+    /// bumped whenever the serialized shape of PL or RQ changes, so external
+    /// tools can reject ASTs they don't understand instead of misreading them.
+    pub const SCHEMA_VERSION: u32 = 2;
+
+    #[derive(Serialize)]
+    struct VersionedRef<'a, T> {
+        schema_version: u32,
+        ast: &'a T,
+    }
+
+    #[derive(Deserialize)]
+    struct Versioned {
+        schema_version: u32,
+        ast: serde_json::Value,
+    }
+
+    /// JSON serialization, wrapped in an object with the schema version
+    pub fn from_pl_versioned(pl: &pr::ModuleDef) -> Result<String, ErrorMessages> {
+        to_versioned(pl)
+    }
+
+    /// JSON deserialization of output of [from_pl_versioned]
+    pub fn to_pl_versioned(json: &str) -> Result<pr::ModuleDef, ErrorMessages> {
+        from_versioned(json)
+    }
+
+    /// JSON serialization, wrapped in an object with the schema version
+    pub fn from_rq_versioned(rq: &ir::rq::RelationalQuery) -> Result<String, ErrorMessages> {
+        to_versioned(rq)
+    }
+
+    /// JSON deserialization of output of [from_rq_versioned]
+    pub fn to_rq_versioned(json: &str) -> Result<ir::rq::RelationalQuery, ErrorMessages> {
+        from_versioned(json)
+    }
+
+    fn to_versioned<T: Serialize>(ast: &T) -> Result<String, ErrorMessages> {
+        let versioned = VersionedRef {
+            schema_version: SCHEMA_VERSION,
+            ast,
+        };
+        serde_json::to_string(&versioned).map_err(convert_json_err)
+    }
+
+    fn from_versioned<T: serde::de::DeserializeOwned>(json: &str) -> Result<T, ErrorMessages> {
+        let versioned: Versioned = serde_json::from_str(json).map_err(convert_json_err)?;
+
+        if versioned.schema_version != SCHEMA_VERSION {
+            return Err(Error::new_simple(format!(
+                "unsupported JSON schema version {}, expected {SCHEMA_VERSION}",
+                versioned.schema_version
+            ))
+            .into());
+        }
+        serde_json::from_value(versioned.ast).map_err(convert_json_err)
+    }
+
     fn convert_json_err(err: serde_json::Error) -> ErrorMessages {
         ErrorMessages::from(Error::new_simple(err.to_string()))
     }
 }
 
+/// Compact binary (CBOR) serialization, for caching ASTs where JSON would be
+/// slow to store and load.
+pub mod binary {
+    use super::*;
+
+    /// Binary serialization
+    pub fn from_pl(pl: &pr::ModuleDef) -> Result<Vec<u8>, ErrorMessages> {
+        to_bytes(pl)
+    }
+
+    /// Binary deserialization
+    pub fn to_pl(bytes: &[u8]) -> Result<pr::ModuleDef, ErrorMessages> {
+        ciborium::from_reader(bytes).map_err(convert_binary_err)
+    }
+
+    /// Binary serialization
+    pub fn from_rq(rq: &ir::rq::RelationalQuery) -> Result<Vec<u8>, ErrorMessages> {
+        to_bytes(rq)
+    }
+
+    /// Binary deserialization
+    pub fn to_rq(bytes: &[u8]) -> Result<ir::rq::RelationalQuery, ErrorMessages> {
+        ciborium::from_reader(bytes).map_err(convert_binary_err)
+    }
+
+    fn to_bytes<T: Serialize>(value: &T) -> Result<Vec<u8>, ErrorMessages> {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(value, &mut bytes).map_err(convert_binary_err)?;
+        Ok(bytes)
+    }
+
+    fn convert_binary_err<E: std::fmt::Display>(err: E) -> ErrorMessages {
+        ErrorMessages::from(Error::new_simple(err.to_string()))
+    }
+}
+
 /// All paths are relative to the project root.
 // We use `SourceTree` to represent both a single file (including a "file" piped
 // from stdin), and a collection of files. (Possibly this could be implemented
@@ -517,14 +794,44 @@ pub mod internal {
 
         let root_module = semantic::resolve(pl).map_err(ErrorMessages::from)?;
 
-        let (main, _) = root_module.find_main_rel(&[]).unwrap();
-        let mut fc =
-            semantic::reporting::collect_frames(*main.clone().into_relation_var().unwrap());
+        let main = main_relation(&root_module)?;
+        let mut fc = semantic::reporting::collect_frames(main);
         fc.ast = ast;
 
         Ok(fc)
     }
 
+    /// Best-effort names and types of the columns of the main relation.
+    ///
+    /// Columns whose type could not be inferred (for example s-strings) have a
+    /// type of `None`. Columns that are not known at all (from a table without
+    /// a declared schema) are represented by a [pr::TyTupleField::Wildcard].
+    pub fn pl_to_output_schema(pl: pr::ModuleDef) -> Result<Vec<pr::TyTupleField>, ErrorMessages> {
+        let root_module = semantic::resolve(pl).map_err(ErrorMessages::from)?;
+
+        let main = main_relation(&root_module)?;
+
+        Ok(main
+            .ty
+            .and_then(|ty| ty.into_relation())
+            .unwrap_or_default())
+    }
+
+    /// The resolved expression of the main relation.
+    fn main_relation(root_module: &ir::decl::RootModule) -> Result<ir::pl::Expr, ErrorMessages> {
+        let (main, ident) = root_module.find_main_rel(&[]).map_err(|(hint, span)| {
+            Error::new_simple("Missing main pipeline")
+                .with_code("E0001")
+                .with_hints(hint)
+                .with_span(span)
+        })?;
+
+        let main = main.clone().into_relation_var().map_err(|_| {
+            Error::new_simple(format!("{ident} is not a relational expression")).with_code("E0001")
+        })?;
+        Ok(*main)
+    }
+
     pub mod json {
         use super::*;
 
@@ -545,9 +852,9 @@ pub mod internal {
 mod tests {
     use std::str::FromStr;
 
-    use insta::assert_debug_snapshot;
+    use insta::{assert_debug_snapshot, assert_snapshot};
 
-    use crate::pr::Ident;
+    use crate::pr::{Ident, TyTupleField};
     use crate::Target;
 
     pub fn compile(prql: &str) -> Result<String, super::ErrorMessages> {
@@ -615,6 +922,49 @@ mod tests {
         "#);
     }
 
+    #[test]
+    fn test_output_schema() {
+        let pl = super::prql_to_pl(
+            r#"
+        from employees
+        group {department} (
+          aggregate {
+            n = count this,
+            avg_salary = average salary,
+          }
+        )
+        derive {label = s"'dept'"}
+        "#,
+        )
+        .unwrap();
+        let schema = super::internal::pl_to_output_schema(pl).unwrap();
+
+        let schema = schema
+            .iter()
+            .map(|field| match field {
+                TyTupleField::Single(name, ty) => format!(
+                    "{} <{}>",
+                    name.as_deref().unwrap_or("?"),
+                    ty.as_ref()
+                        .map_or("?".to_string(), crate::codegen::write_ty)
+                ),
+                TyTupleField::Wildcard(_) => "*".to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_snapshot!(schema, @r"
+        department <?>
+        n <int>
+        avg_salary <float || null>
+        label <?>
+        ");
+
+        // without a main pipeline, there is no schema to infer
+        let pl = super::prql_to_pl("let x = 5").unwrap();
+        let err = super::internal::pl_to_output_schema(pl).unwrap_err();
+        assert!(err.to_string().contains("Missing main pipeline"));
+    }
+
     /// Confirm that all target names can be parsed.
     #[test]
     fn test_target_names() {
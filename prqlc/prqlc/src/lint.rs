@@ -0,0 +1,335 @@
+//! Lints find likely mistakes in queries that compile successfully.
+//!
+//! They run over RQ and produce messages of kind [MessageKind::Lint], which
+//! have a code and a span, same as errors do.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::ir::pl::{JoinSide, Literal};
+use crate::ir::rq::{self, CId, Expr, ExprKind, RelationalQuery, RqVisitor, Transform};
+use crate::{Error, MessageKind, Span, WithErrorInfo};
+
+#[derive(
+    Debug,
+    PartialEq,
+    Eq,
+    Clone,
+    Copy,
+    strum::Display,
+    strum::EnumIter,
+    strum::EnumString,
+    strum::VariantNames,
+)]
+#[strum(serialize_all = "kebab-case")]
+pub enum Lint {
+    /// A column is computed, but not used by any following transform or
+    /// included in the output.
+    UnusedColumn,
+
+    /// Rows are taken from a relation that is not sorted, so which rows are
+    /// returned is up to the database.
+    UnsortedTake,
+
+    /// A filter after an outer join removes the rows where the nullable side
+    /// had no match, which makes the join behave as an inner join.
+    OuterJoinFilter,
+
+    /// A negated `in` checks against an array that contains null, so it is
+    /// never true and removes all rows.
+    NotInNull,
+}
+
+impl Lint {
+    /// Machine-readable identifier of the lint, used as the message code.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Lint::UnusedColumn => "L001",
+            Lint::UnsortedTake => "L002",
+            Lint::OuterJoinFilter => "L003",
+            Lint::NotInNull => "L004",
+        }
+    }
+
+    pub fn all() -> Vec<Lint> {
+        use strum::IntoEnumIterator;
+        Lint::iter().collect()
+    }
+}
+
+/// Runs the given lints over a query, returning a message for each finding.
+pub fn lint(query: &RelationalQuery, lints: &[Lint]) -> Vec<Error> {
+    let mut messages = Vec::new();
+
+    if lints.contains(&Lint::UnusedColumn) {
+        messages.extend(unused_columns(query));
+    }
+    if lints.contains(&Lint::UnsortedTake) {
+        messages.extend(unsorted_takes(query));
+    }
+    if lints.contains(&Lint::OuterJoinFilter) {
+        messages.extend(outer_join_filters(query));
+    }
+    if lints.contains(&Lint::NotInNull) {
+        messages.extend(not_in_nulls(query));
+    }
+
+    messages.sort_by_key(|e| e.span.map(|s| (s.source_id, s.start)));
+    messages
+}
+
+fn new_lint(lint: Lint, message: String) -> Error {
+    let mut error = Error::new_simple(message).with_code(lint.code());
+    error.kind = MessageKind::Lint;
+    error
+}
+
+fn unused_columns(query: &RelationalQuery) -> Vec<Error> {
+    let mut collector = ColumnUsage::default();
+    collector.visit_query(query);
+
+    collector
+        .computes
+        .into_iter()
+        .filter(|(cid, _)| !collector.used.contains(cid))
+        .map(|(_, span)| {
+            new_lint(
+                Lint::UnusedColumn,
+                "this column is computed, but never used".to_string(),
+            )
+            .push_hint("remove it, or include it in the output with `select`")
+            .with_span(Some(span))
+        })
+        .collect()
+}
+
+/// Collects computed columns and the columns that are referenced.
+#[derive(Default)]
+struct ColumnUsage {
+    computes: Vec<(CId, Span)>,
+    used: HashSet<CId>,
+}
+
+impl RqVisitor for ColumnUsage {
+    fn visit_cid(&mut self, cid: CId) {
+        self.used.insert(cid);
+    }
+
+    fn visit_compute(&mut self, compute: &rq::Compute) {
+        // only computes written by the user have a span; others are generated
+        // by the compiler
+        if let Some(span) = compute.expr.span.filter(|_| !compute.is_aggregation) {
+            self.computes.push((compute.id, span));
+        }
+
+        // the id is a declaration, not a use
+        let used_before = self.used.contains(&compute.id);
+        rq::visit_compute(self, compute);
+        if !used_before {
+            self.used.remove(&compute.id);
+        }
+    }
+
+    fn visit_table_ref(&mut self, _table_ref: &rq::TableRef) {
+        // columns of table refs are declarations, not uses
+    }
+}
+
+fn unsorted_takes(query: &RelationalQuery) -> Vec<Error> {
+    let mut collector = UnsortedTakes::default();
+    collector.visit_query(query);
+    collector.messages
+}
+
+#[derive(Default)]
+struct UnsortedTakes {
+    messages: Vec<Error>,
+}
+
+impl RqVisitor for UnsortedTakes {
+    fn visit_transform(&mut self, transform: &Transform) {
+        if let Transform::Take(take) = transform {
+            if take.sort.is_empty() && take.partition.is_empty() {
+                let span = (take.range.end.as_ref())
+                    .or(take.range.start.as_ref())
+                    .and_then(|e| e.span);
+
+                self.messages.push(
+                    new_lint(
+                        Lint::UnsortedTake,
+                        "`take` of an unsorted relation returns arbitrary rows".to_string(),
+                    )
+                    .push_hint("add a `sort` before the `take`")
+                    .with_span(span),
+                );
+            }
+        }
+        rq::visit_transform(self, transform)
+    }
+}
+
+fn outer_join_filters(query: &RelationalQuery) -> Vec<Error> {
+    let mut collector = OuterJoinFilters::default();
+    collector.visit_query(query);
+    collector.messages
+}
+
+/// Finds filters on the nullable side of an outer join.
+///
+/// Such filters are applied in WHERE, after the join, so they remove the rows
+/// that the join did not match. Filters that compare to null (or coalesce it)
+/// are assumed to handle unmatched rows on purpose. Tracking is reset after an
+/// aggregate.
+#[derive(Default)]
+struct OuterJoinFilters {
+    messages: Vec<Error>,
+}
+
+impl RqVisitor for OuterJoinFilters {
+    fn visit_transforms(&mut self, transforms: &[Transform]) {
+        // relation names of the columns that may be null due to an outer join
+        let mut nullable: HashMap<CId, String> = HashMap::new();
+        let mut inputs: Vec<(CId, String)> = Vec::new();
+
+        for transform in transforms {
+            match transform {
+                Transform::From(table_ref) => {
+                    inputs.extend(table_ref_columns(table_ref));
+                }
+                Transform::Join { side, with, .. } => {
+                    let joined = table_ref_columns(with);
+                    if matches!(side, JoinSide::Right | JoinSide::Full) {
+                        nullable.extend(inputs.iter().cloned());
+                    }
+                    if matches!(side, JoinSide::Left | JoinSide::Full) {
+                        nullable.extend(joined.iter().cloned());
+                    }
+                    inputs.extend(joined);
+                }
+                Transform::Compute(compute) if !compute.is_aggregation => {
+                    let relation = (collect_cids(&compute.expr).iter())
+                        .find_map(|cid| nullable.get(cid).cloned());
+                    if let Some(relation) = relation {
+                        nullable.insert(compute.id, relation);
+                    }
+                }
+                Transform::Aggregate { .. } => nullable.clear(),
+                Transform::Filter(filter) if !handles_null(filter) => {
+                    let relation =
+                        (collect_cids(filter).iter()).find_map(|cid| nullable.get(cid).cloned());
+                    if let Some(relation) = relation {
+                        self.messages.push(
+                            new_lint(
+                                Lint::OuterJoinFilter,
+                                format!(
+                                    "this filter removes the rows where `{relation}` had no \
+                                     match, as an inner join would"
+                                ),
+                            )
+                            .push_hint("move the condition into the join to keep them")
+                            .with_span(filter.span),
+                        );
+                    }
+                }
+                _ => {}
+            }
+            self.visit_transform(transform);
+        }
+    }
+}
+
+fn table_ref_columns(table_ref: &rq::TableRef) -> Vec<(CId, String)> {
+    let name = table_ref
+        .name
+        .clone()
+        .unwrap_or_else(|| "the relation".to_string());
+    (table_ref.columns.iter())
+        .map(|(_, cid)| (*cid, name.clone()))
+        .collect()
+}
+
+fn collect_cids(expr: &Expr) -> Vec<CId> {
+    struct CidCollector(Vec<CId>);
+
+    impl RqVisitor for CidCollector {
+        fn visit_cid(&mut self, cid: CId) {
+            self.0.push(cid);
+        }
+    }
+
+    let mut collector = CidCollector(Vec::new());
+    collector.visit_expr(expr);
+    collector.0
+}
+
+fn handles_null(expr: &Expr) -> bool {
+    struct NullFinder(bool);
+
+    impl RqVisitor for NullFinder {
+        fn visit_expr(&mut self, expr: &Expr) {
+            match &expr.kind {
+                ExprKind::Literal(Literal::Null) => self.0 = true,
+                ExprKind::Operator { name, .. } if name == "std.coalesce" => self.0 = true,
+                kind => rq::visit_expr_kind(self, kind),
+            }
+        }
+    }
+
+    let mut finder = NullFinder(false);
+    finder.visit_expr(expr);
+    finder.0
+}
+
+fn not_in_nulls(query: &RelationalQuery) -> Vec<Error> {
+    let mut collector = NotInNulls::default();
+    collector.visit_query(query);
+    collector.messages
+}
+
+/// Finds negated `in` checks against arrays that contain a null literal.
+///
+/// `x NOT IN (1, NULL)` is never true in SQL, because `x <> NULL` is null.
+/// Only literal nulls are found, since whether a column or a parameter is null
+/// is not known when compiling. A relation cannot be used in a negated `in`
+/// (that's an anti join, which compiles to NOT EXISTS and is not affected by
+/// nulls), so `NOT IN (SELECT ...)` is never produced.
+#[derive(Default)]
+struct NotInNulls {
+    messages: Vec<Error>,
+}
+
+impl RqVisitor for NotInNulls {
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let ExprKind::Operator { name, args } = &expr.kind {
+            if let [Expr {
+                kind:
+                    ExprKind::Operator {
+                        name: inner,
+                        args: in_args,
+                    },
+                ..
+            }] = args.as_slice()
+            {
+                if name == "std.not" && inner == "std.array_in" && contains_null(&in_args[1]) {
+                    self.messages.push(
+                        new_lint(
+                            Lint::NotInNull,
+                            "a negated `in` against an array containing null is never true, \
+                             so it removes every row"
+                                .to_string(),
+                        )
+                        .push_hint("remove the null from the array and check for it separately")
+                        .with_span(expr.span),
+                    );
+                }
+            }
+        }
+        rq::visit_expr_kind(self, &expr.kind)
+    }
+}
+
+fn contains_null(array: &Expr) -> bool {
+    let ExprKind::Array(items) = &array.kind else {
+        return false;
+    };
+    (items.iter()).any(|item| matches!(item.kind, ExprKind::Literal(Literal::Null)))
+}
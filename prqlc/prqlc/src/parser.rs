@@ -1,4 +1,5 @@
-use std::path::PathBuf;
+use std::ops::Range;
+use std::path::{Component, PathBuf};
 use std::{collections::HashMap, path::Path};
 
 use itertools::Itertools;
@@ -58,8 +59,26 @@ pub fn parse(file_tree: &SourceTree) -> Result<pr::ModuleDef, Errors> {
 // out into a separate crate, but it has dependencies on `prqlc` internals and
 // would be an effort)
 pub(crate) fn parse_source(source: &str, source_id: u16) -> Result<Vec<pr::Stmt>, Vec<Error>> {
+    parse_source_at(source, source_id, 0)
+}
+
+/// Parses source that starts at `offset` characters into a file.
+fn parse_source_at(
+    source: &str,
+    source_id: u16,
+    offset: usize,
+) -> Result<Vec<pr::Stmt>, Vec<Error>> {
     let (tokens, mut errors) = prqlc_parser::lexer::lex_source_recovery(source, source_id);
 
+    let tokens = tokens.map(|tokens| {
+        (tokens.into_iter())
+            .map(|t| lr::Token {
+                span: t.span.start + offset..t.span.end + offset,
+                ..t
+            })
+            .collect_vec()
+    });
+
     let ast = if let Some(tokens) = tokens {
         debug::log_entry(|| debug::DebugEntryKind::ReprLr(lr::Tokens(tokens.clone())));
 
@@ -77,6 +96,174 @@ pub(crate) fn parse_source(source: &str, source_id: u16) -> Result<Vec<pr::Stmt>
     }
 }
 
+/// A replacement of a range of a source, as editors send on each change.
+///
+/// Like [crate::Span], the range is in characters rather than bytes.
+#[derive(Debug, Clone)]
+pub struct SourceEdit {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+/// Parses a source after an edit, reusing statements of the previous parse.
+///
+/// Returns the edited source and its statements. Statements before the edit
+/// are reused, except for the one just before it, since an edit can continue
+/// it (for example, by adding a transform to a main pipeline). Everything from
+/// there on is parsed again, because the spans of the statements after the
+/// edit change.
+pub fn reparse_source(
+    prev_source: &str,
+    prev_stmts: &[pr::Stmt],
+    edit: &SourceEdit,
+    source_id: u16,
+) -> (String, Result<Vec<pr::Stmt>, Vec<Error>>) {
+    let source: String = (prev_source.chars().take(edit.range.start))
+        .chain(edit.text.chars())
+        .chain(prev_source.chars().skip(edit.range.end))
+        .collect();
+
+    let before_edit = (prev_stmts.iter())
+        .take_while(|s| s.span.map_or(false, |span| span.end < edit.range.start))
+        .count();
+    let reused = before_edit.saturating_sub(1);
+    let start = reused
+        .checked_sub(1)
+        .and_then(|i| prev_stmts[i].span)
+        .map_or(0, |span| span.end);
+
+    if start == 0 {
+        let stmts = parse_source(&source, source_id);
+        return (source, stmts);
+    }
+
+    let rest: String = source.chars().skip(start).collect();
+    let stmts = match parse_source_at(&rest, source_id, start) {
+        Ok(stmts) => Ok(prev_stmts[..reused].iter().cloned().chain(stmts).collect()),
+        // the rest may not parse on its own (for example when it starts with
+        // the `;` that ends the previous statement), so any errors are reported
+        // from parsing the whole source
+        Err(_) => parse_source(&source, source_id),
+    };
+    (source, stmts)
+}
+
+/// Provides sources of modules that are imported, but are not in the
+/// [SourceTree] yet.
+///
+/// The default is [FileModuleLoader]; embedders can implement this to load
+/// modules from elsewhere.
+pub trait ModuleLoader {
+    /// Returns the source at `path` (relative to the root of the tree), or
+    /// `None` if there is no such source.
+    fn load(&self, path: &Path) -> Result<Option<String>>;
+}
+
+/// Loads modules from `.prql` files in a directory.
+#[derive(Debug, Clone)]
+pub struct FileModuleLoader {
+    pub root: PathBuf,
+}
+
+impl ModuleLoader for FileModuleLoader {
+    fn load(&self, path: &Path) -> Result<Option<String>> {
+        if !path.components().all(|c| matches!(c, Component::Normal(_))) {
+            return Err(Error::new_simple(format!(
+                "cannot load `{}`: not a path within the root",
+                path.display()
+            )));
+        }
+        match std::fs::read_to_string(self.root.join(path)) {
+            Ok(source) => Ok(Some(source)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(Error::new_simple(format!(
+                "cannot read `{}`: {e}",
+                path.display()
+            ))),
+        }
+    }
+}
+
+impl ModuleLoader for HashMap<PathBuf, String> {
+    fn load(&self, path: &Path) -> Result<Option<String>> {
+        Ok(self.get(path).cloned())
+    }
+}
+
+/// Adds sources of imported modules to the tree, until all imports of all
+/// sources are loaded.
+///
+/// `import a.b.c` loads any of `a.prql`, `a/b.prql` and `a/b/c.prql` that the
+/// loader provides, so they become modules `a`, `a.b` and `a.b.c`. Each file
+/// is loaded once, so modules may import each other. Modules declared with a
+/// `module` statement in the importing source are not loaded; if a file for
+/// such module exists too, that's an error.
+///
+/// Sources that fail to parse are skipped; their errors are reported when the
+/// tree is parsed.
+pub fn load_imports(tree: &mut SourceTree, loader: &dyn ModuleLoader) -> Result<(), Errors> {
+    let mut queue: Vec<PathBuf> = tree.sources.keys().cloned().collect();
+    queue.sort();
+
+    while let Some(path) = queue.pop() {
+        let Ok(stmts) = parse_source(&tree.sources[&path], 0) else {
+            continue;
+        };
+
+        let mut imports = Vec::new();
+        collect_imports(&stmts, &mut imports);
+
+        for import in imports {
+            let declared = stmts.iter().any(|s| is_mod_def_for(s, &import[0]));
+
+            for len in 1..=import.len() {
+                let module_path = &import[..len];
+                if module_path[0] == "std" {
+                    break;
+                }
+
+                let file_path = prql_path_to_os_path(module_path)?;
+                if tree.sources.contains_key(&file_path) {
+                    continue;
+                }
+                let Some(source) = loader.load(&file_path)? else {
+                    continue;
+                };
+
+                if declared {
+                    return Err(Error::new_simple(format!(
+                        "module `{}` is declared in `{}`, but also exists as `{}`",
+                        import[0],
+                        path.display(),
+                        file_path.display()
+                    ))
+                    .push_hint("rename either of them")
+                    .into());
+                }
+
+                tree.insert(file_path.clone(), source);
+                queue.push(file_path);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Collects paths of all imports, including those within module definitions.
+fn collect_imports(stmts: &[pr::Stmt], imports: &mut Vec<Vec<String>>) {
+    for stmt in stmts {
+        match &stmt.kind {
+            pr::StmtKind::ImportDef(import) => {
+                let mut path = import.name.path.clone();
+                path.push(import.name.name.clone());
+                imports.push(path);
+            }
+            pr::StmtKind::ModuleDef(module) => collect_imports(&module.stmts, imports),
+            _ => {}
+        }
+    }
+}
+
 struct SourceFile<'a> {
     file_path: &'a Path,
     module_path: Vec<String>,
@@ -176,6 +363,73 @@ fn insert_stmts_at_path(module: &mut pr::ModuleDef, mut path: Vec<String>, stmts
     insert_stmts_at_path(submodule, path, stmts);
 }
 
+/// Splits a module with multiple main pipelines into one module per main
+/// pipeline. All other statements are shared between the resulting modules.
+pub(crate) fn split_queries(root: pr::ModuleDef) -> Vec<pr::ModuleDef> {
+    let (mains, others): (Vec<_>, Vec<_>) = root.stmts.into_iter().partition(is_main);
+
+    if mains.len() <= 1 {
+        let mut stmts = others;
+        stmts.extend(mains);
+        return vec![pr::ModuleDef {
+            name: root.name,
+            stmts,
+        }];
+    }
+
+    mains
+        .into_iter()
+        .map(|main| {
+            let mut stmts = others.clone();
+            stmts.push(main);
+            pr::ModuleDef {
+                name: root.name.clone(),
+                stmts,
+            }
+        })
+        .collect()
+}
+
+/// Wraps the main expression of a fragment into a query that selects just
+/// that expression, from a relation named `_fragment`.
+pub(crate) fn wrap_fragment(mut root: pr::ModuleDef) -> Result<pr::ModuleDef> {
+    let main = (root.stmts.iter_mut())
+        .find(|stmt| is_main(stmt))
+        .and_then(|stmt| stmt.kind.as_var_def_mut())
+        .and_then(|def| def.value.as_mut())
+        .ok_or_else(|| Error::new_simple("expected an expression"))?;
+
+    let call = |name: &str, arg: pr::Expr| {
+        pr::Expr::new(pr::ExprKind::FuncCall(pr::FuncCall {
+            name: Box::new(pr::Expr::new(pr::ExprKind::Ident(name.to_string()))),
+            args: vec![arg],
+            named_args: HashMap::new(),
+        }))
+    };
+
+    let expr = std::mem::replace(main.as_mut(), pr::Expr::new(pr::ExprKind::Tuple(vec![])));
+    let span = expr.span;
+    let from = call(
+        "from",
+        pr::Expr::new(pr::ExprKind::Ident("_fragment".into())),
+    );
+    let select = call("select", pr::Expr::new(pr::ExprKind::Tuple(vec![expr])));
+
+    **main = pr::Expr {
+        span,
+        ..pr::Expr::new(pr::ExprKind::Pipeline(pr::Pipeline {
+            exprs: vec![from, select],
+        }))
+    };
+    Ok(root)
+}
+
+fn is_main(stmt: &pr::Stmt) -> bool {
+    stmt.kind
+        .as_var_def()
+        .map_or(false, |x| x.kind == pr::VarDefKind::Main)
+}
+
 pub(crate) fn is_mod_def_for(stmt: &pr::Stmt, name: &str) -> bool {
     stmt.kind.as_module_def().map_or(false, |x| x.name == name)
 }
@@ -202,3 +456,119 @@ pub fn os_path_to_prql_path(path: &Path) -> Result<Vec<String>> {
         })
         .try_collect()
 }
+
+/// Converts a module path to the path of its file, relative to the root of
+/// the tree. Names that are not a single plain file name (such as `..` or
+/// ones containing `/`) are rejected, so imports cannot read files outside of
+/// the root.
+fn prql_path_to_os_path(path: &[String]) -> Result<PathBuf> {
+    for name in path {
+        let mut components = Path::new(name).components();
+        let is_plain = matches!(components.next(), Some(Component::Normal(c)) if c == name.as_str())
+            && components.next().is_none()
+            && !name.contains(['/', '\\']);
+        if !is_plain {
+            return Err(Error::new_simple(format!(
+                "cannot import `{name}`: module names cannot be paths"
+            )));
+        }
+    }
+
+    let mut res: PathBuf = path.iter().collect();
+    res.set_extension("prql");
+    Ok(res)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_reparse(prev_source: &str, range: Range<usize>, text: &str) {
+        let prev_stmts = parse_source(prev_source, 0).unwrap();
+        let edit = SourceEdit {
+            range,
+            text: text.to_string(),
+        };
+
+        let (source, stmts) = reparse_source(prev_source, &prev_stmts, &edit, 0);
+        assert_eq!(stmts.unwrap(), parse_source(&source, 0).unwrap());
+    }
+
+    #[test]
+    fn test_reparse_source() {
+        let source = r#"
+let a = 5
+
+let b = 6
+
+from x
+select {a, b}
+"#;
+        let take = source.find("select").unwrap();
+
+        // in the main pipeline
+        assert_reparse(source, take..take, "take 10\n");
+        // continuing the statement before the edit
+        assert_reparse(source, source.len()..source.len(), "take 10\n");
+        // in the middle
+        let b = source.find("6").unwrap();
+        assert_reparse(source, b..b + 1, "a + 1");
+        // at the start
+        assert_reparse(source, 0..0, "prql target:sql.sqlite\n");
+
+        let (source, stmts) = reparse_source(
+            source,
+            &parse_source(source, 0).unwrap(),
+            &SourceEdit {
+                range: take..take,
+                text: "take (".to_string(),
+            },
+            0,
+        );
+        assert!(source.contains("take (select"));
+        assert!(stmts.is_err());
+    }
+
+    #[test]
+    fn test_load_imports() {
+        let files: HashMap<PathBuf, String> = [
+            ("lib.prql", "import util\nlet double = x -> x * 2"),
+            ("util.prql", "import lib\nlet one = 1"),
+            ("orders.prql", "let x = 1"),
+        ]
+        .into_iter()
+        .map(|(p, s)| (PathBuf::from(p), s.to_string()))
+        .collect();
+
+        let mut tree = SourceTree::from("import lib.double\nfrom t | select {double a}");
+        load_imports(&mut tree, &files).unwrap();
+
+        // lib imports util, which imports lib again; orders is not imported
+        let paths = tree.sources.keys().sorted().collect_vec();
+        assert_eq!(
+            paths,
+            [
+                &PathBuf::from(""),
+                &PathBuf::from("lib.prql"),
+                &PathBuf::from("util.prql")
+            ]
+        );
+
+        let mut tree = SourceTree::from("module lib {}\nimport lib\nfrom t");
+        let err = load_imports(&mut tree, &files).unwrap_err();
+        assert!(err.to_string().contains("module `lib` is declared"));
+
+        // quoted names cannot escape the root
+        for import in ["import `../evil`", "import `/etc/passwd`", "import a.`..`"] {
+            let mut tree = SourceTree::from(format!("{import}\nfrom t"));
+            let err = load_imports(&mut tree, &files).unwrap_err();
+            assert!(err.to_string().contains("cannot be paths"), "{import}");
+        }
+
+        let loader = FileModuleLoader {
+            root: PathBuf::from("."),
+        };
+        assert!(loader.load(Path::new("../evil.prql")).is_err());
+        assert!(loader.load(Path::new("/etc/passwd")).is_err());
+    }
+}
@@ -49,15 +49,29 @@ pub fn expand_expr(expr: pr::Expr) -> Result<pl::Expr> {
         pr::ExprKind::Unary(unary) => expand_unary(unary)?,
         pr::ExprKind::Binary(binary) => expand_binary(binary)?,
 
-        pr::ExprKind::FuncCall(v) => pl::ExprKind::FuncCall(pl::FuncCall {
-            name: expand_expr_box(v.name)?,
-            args: expand_exprs(v.args)?,
-            named_args: v
-                .named_args
-                .into_iter()
-                .map(|(k, v)| -> Result<_> { Ok((k, expand_expr(v)?)) })
-                .try_collect()?,
-        }),
+        pr::ExprKind::FuncCall(mut v) => {
+            if is_std_transform(&v.name, "sort") {
+                v.args = v.args.into_iter().map(desugar_sort_keywords).collect();
+            }
+            if is_std_transform(&v.name, "take") {
+                desugar_take_keywords(&mut v);
+            }
+            if is_std_transform(&v.name, "group") {
+                if let Some(by) = v.args.first_mut() {
+                    desugar_group_all(by);
+                }
+            }
+
+            pl::ExprKind::FuncCall(pl::FuncCall {
+                name: expand_expr_box(v.name)?,
+                args: expand_exprs(v.args)?,
+                named_args: v
+                    .named_args
+                    .into_iter()
+                    .map(|(k, v)| -> Result<_> { Ok((k, expand_expr(v)?)) })
+                    .try_collect()?,
+            })
+        }
         pr::ExprKind::Func(v) => pl::ExprKind::Func(
             pl::Func {
                 return_ty: v.return_ty,
@@ -152,6 +166,68 @@ fn desugar_pipeline(mut pipeline: pr::Pipeline) -> Result<pl::Expr> {
     Ok(value)
 }
 
+fn is_std_transform(name: &pr::Expr, transform: &str) -> bool {
+    match &name.kind {
+        pr::ExprKind::Ident(name) => name == transform,
+        pr::ExprKind::Indirection { base, field } => {
+            matches!(&base.kind, pr::ExprKind::Ident(base) if base == "std")
+                && matches!(field, pr::IndirectionKind::Name(field) if field == transform)
+        }
+        _ => false,
+    }
+}
+
+/// Desugar `group all` into grouping by all columns of the relation.
+fn desugar_group_all(by: &mut pr::Expr) {
+    if matches!(&by.kind, pr::ExprKind::Ident(name) if name == "all") {
+        by.kind = pr::ExprKind::Ident(NS_THIS.to_string());
+    }
+}
+
+/// Desugar `take first 5` and `take last 5` into `take rows:first 5` and
+/// `take rows:last 5`.
+fn desugar_take_keywords(call: &mut pr::FuncCall) {
+    if call.args.len() < 2 || call.named_args.contains_key("rows") {
+        return;
+    }
+    if matches!(&call.args[0].kind, pr::ExprKind::Ident(kw) if kw == "first" || kw == "last") {
+        let keyword = call.args.remove(0);
+        call.named_args.insert("rows".to_string(), keyword);
+    }
+}
+
+/// Desugar `column desc` and `column asc` within the arg of `sort` into
+/// `-column` and `+column`.
+fn desugar_sort_keywords(by: pr::Expr) -> pr::Expr {
+    let kind = match by.kind {
+        pr::ExprKind::Tuple(fields) => {
+            pr::ExprKind::Tuple(fields.into_iter().map(desugar_sort_keywords).collect())
+        }
+        pr::ExprKind::FuncCall(call) if sort_keyword(&call).is_some() => {
+            pr::ExprKind::Unary(pr::UnaryExpr {
+                op: sort_keyword(&call).unwrap(),
+                expr: call.name,
+            })
+        }
+        kind => kind,
+    };
+    pr::Expr { kind, ..by }
+}
+
+fn sort_keyword(call: &pr::FuncCall) -> Option<pr::UnOp> {
+    if !call.named_args.is_empty() {
+        return None;
+    }
+    let [arg] = call.args.as_slice() else {
+        return None;
+    };
+    match arg.kind.as_ident()?.as_str() {
+        "asc" => Some(pr::UnOp::Add),
+        "desc" => Some(pr::UnOp::Neg),
+        _ => None,
+    }
+}
+
 /// Desugar unary operators into function calls.
 fn expand_unary(pr::UnaryExpr { op, expr }: pr::UnaryExpr) -> Result<pl::ExprKind> {
     use pr::UnOp::*;
@@ -469,6 +545,7 @@ fn restrict_stmt(stmt: pl::Stmt) -> pr::Stmt {
             .map(restrict_annotation)
             .collect(),
         doc_comment: None,
+        comments: Vec::new(),
     }
 }
 
@@ -10,9 +10,9 @@ use semver::{Prerelease, Version};
 
 use crate::compiler_version;
 use crate::ir::decl::{self, DeclKind, Module, RootModule, TableExpr};
-use crate::ir::generic::{ColumnSort, WindowFrame};
+use crate::ir::generic::{ColumnSort, SortDirection, WindowFrame, WindowKind};
 use crate::ir::pl::TableExternRef::LocalTable;
-use crate::ir::pl::{self, Ident, Lineage, LineageColumn, PlFold, QueryDef};
+use crate::ir::pl::{self, Ident, JoinSide, Lineage, LineageColumn, PlFold, QueryDef};
 use crate::ir::rq::{
     self, CId, RelationColumn, RelationLiteral, RelationalQuery, TId, TableDecl, Transform,
 };
@@ -49,8 +49,11 @@ pub fn lower_to_ir(
 
     // find & validate query def
     let def = root_mod.find_query_def(&main_ident);
-    let def = def.cloned().unwrap_or_default();
+    let mut def = def.cloned().unwrap_or_default();
     validate_query_def(&def)?;
+    if let Some(main) = root_mod.module.get(&main_ident) {
+        def.metadata = query_metadata(main);
+    }
 
     // find all tables in the root module
     let tables = TableExtractor::extract(&root_mod.module);
@@ -124,6 +127,23 @@ fn tuple_fields_to_relation_columns(columns: Vec<TyTupleField>) -> Vec<RelationC
         .collect_vec()
 }
 
+/// Collects the annotations of the main pipeline that have the form
+/// `@key "value"`.
+fn query_metadata(main: &decl::Decl) -> HashMap<String, String> {
+    let mut metadata = HashMap::new();
+    for annotation in &main.annotations {
+        let pl::ExprKind::FuncCall(call) = &annotation.expr.kind else {
+            continue;
+        };
+        if let (pl::ExprKind::Ident(key), [value]) = (&call.name.kind, call.args.as_slice()) {
+            if let pl::ExprKind::Literal(Literal::String(value)) = &value.kind {
+                metadata.insert(key.to_string(), value.clone());
+            }
+        }
+    }
+    metadata
+}
+
 fn validate_query_def(query_def: &QueryDef) -> Result<()> {
     if let Some(requirement) = &query_def.version {
         let current_version = compiler_version();
@@ -539,17 +559,52 @@ impl Lowerer {
                 let cids = self.declare_as_columns(*assigns, false)?;
                 self.pipeline.push(Transform::Select(cids));
             }
+            pl::TransformKind::Rename { assigns } => {
+                self.declare_as_columns(*assigns, false)?;
+
+                let mut transforms = Vec::new();
+                self.push_select(ast.lineage.clone(), &mut transforms)?;
+                self.pipeline.extend(transforms);
+            }
             pl::TransformKind::Filter { filter, .. } => {
-                let filter = self.lower_expr(*filter)?;
+                let (in_relations, filter) = split_in_relations(*filter);
+
+                for (value, relation, span) in in_relations {
+                    self.lower_in_relation(value, relation, span)?;
+                }
+                if let Some(filter) = filter {
+                    let filter = self.lower_expr(filter)?;
 
-                self.pipeline.push(Transform::Filter(filter));
+                    self.pipeline.push(Transform::Filter(filter));
+                }
+            }
+            pl::TransformKind::Assert { condition } => {
+                self.window = None;
+                self.lower_assert(*condition, ast.id.unwrap(), ast.lineage.clone(), ast.span)?;
             }
             pl::TransformKind::Aggregate { assigns, .. } => {
-                let window = self.window.take();
+                let window = self.window.take().unwrap();
+
+                let (value_fns, assigns) = split_value_functions(*assigns);
+
+                // `first` and `last` are not aggregate functions in SQL, so they are
+                // computed as window functions over the sorted group and then added
+                // to the grouping key (they are constant within each group).
+                let mut partition = window.partition.clone();
+                for expr in value_fns {
+                    self.window = Some(rq::Window {
+                        frame: WindowFrame {
+                            kind: WindowKind::Rows,
+                            range: Range::unbounded(),
+                        },
+                        ..window.clone()
+                    });
+                    partition.push(self.declare_as_column(expr, false)?);
+                }
+                self.window = None;
 
-                let compute = self.declare_as_columns(*assigns, true)?;
+                let compute = self.declare_as_columns(assigns, true)?;
 
-                let partition = window.unwrap().partition;
                 self.pipeline
                     .push(Transform::Aggregate { partition, compute });
             }
@@ -557,17 +612,52 @@ impl Lowerer {
                 let sorts = self.lower_sorts(by)?;
                 self.pipeline.push(Transform::Sort(sorts));
             }
-            pl::TransformKind::Take { range, .. } => {
+            pl::TransformKind::Take { range, sorted } => {
                 let window = self.window.take().unwrap_or_default();
                 let range = self.lower_range(range)?;
 
-                validate_take_range(&range, ast.span)?;
+                if sorted && window.sort.is_empty() {
+                    return Err(Error::new_simple(
+                        "`take first` and `take last` require the relation to be sorted",
+                    )
+                    .push_hint("add a `sort` before the `take`")
+                    .with_span(ast.span));
+                }
+
+                if let Some(n) = take_from_bottom(&range) {
+                    // take the last rows by reversing the sort, and then
+                    // restoring it
+                    if window.sort.is_empty() {
+                        return Err(Error::new_simple(
+                            "taking rows from the bottom requires the relation to be sorted",
+                        )
+                        .push_hint("add a `sort` before the `take`")
+                        .with_span(ast.span));
+                    }
+                    let reversed: Vec<_> = window.sort.iter().cloned().map(reverse_sort).collect();
+
+                    self.pipeline.push(Transform::Sort(reversed.clone()));
+                    self.pipeline.push(Transform::Take(rq::Take {
+                        range: Range {
+                            start: None,
+                            end: Some(rq::Expr {
+                                kind: rq::ExprKind::Literal(Literal::Integer(n)),
+                                span: ast.span,
+                            }),
+                        },
+                        partition: window.partition,
+                        sort: reversed,
+                    }));
+                    self.pipeline.push(Transform::Sort(window.sort));
+                } else {
+                    validate_take_range(&range, ast.span)?;
 
-                self.pipeline.push(Transform::Take(rq::Take {
-                    range,
-                    partition: window.partition,
-                    sort: window.sort,
-                }));
+                    self.pipeline.push(Transform::Take(rq::Take {
+                        range,
+                        partition: window.partition,
+                        sort: window.sort,
+                    }));
+                }
             }
             pl::TransformKind::Join {
                 side, with, filter, ..
@@ -606,6 +696,155 @@ impl Lowerer {
         Ok(())
     }
 
+    /// Lowers `value | in relation` into a semi join, which keeps the rows whose
+    /// value is in the single column of the relation.
+    fn lower_in_relation(
+        &mut self,
+        value: pl::Expr,
+        relation: pl::Expr,
+        span: Option<Span>,
+    ) -> Result<()> {
+        let with = self.lower_table_ref(relation)?;
+
+        let column = match with.columns.as_slice() {
+            [(RelationColumn::Single(_), cid)] => *cid,
+            _ => {
+                return Err(
+                    Error::new_simple("`in` requires a relation with exactly one column")
+                        .push_hint("add a `select` of the column to the relation")
+                        .with_span(span),
+                )
+            }
+        };
+
+        let filter = rq::Expr {
+            kind: rq::ExprKind::Operator {
+                name: "std.array_in".to_string(),
+                args: vec![
+                    self.lower_expr(value)?,
+                    rq::Expr {
+                        kind: rq::ExprKind::ColumnRef(column),
+                        span: None,
+                    },
+                ],
+            },
+            span,
+        };
+        self.pipeline.push(Transform::Join {
+            side: JoinSide::Semi,
+            with,
+            filter,
+        });
+        Ok(())
+    }
+
+    /// Lowers `assert` by pulling the input out into a table, which is then
+    /// joined with a single-row aggregation of itself:
+    ///
+    /// ```ignore
+    /// from input
+    /// join (from input | aggregate {_assert = std.assert condition}) (==1)
+    /// ```
+    ///
+    /// When the condition does not hold, `std.assert` raises an error, which
+    /// fails the whole query. Conditions without an aggregation must hold for
+    /// each of the rows.
+    fn lower_assert(
+        &mut self,
+        condition: pl::Expr,
+        id: usize,
+        lineage: Option<Lineage>,
+        span: Option<Span>,
+    ) -> Result<()> {
+        let mut transforms = self.pipeline.drain(..).collect_vec();
+        let columns = self.push_select(lineage, &mut transforms).with_span(span)?;
+        let cids = transforms.last().unwrap().as_select().unwrap().clone();
+
+        let input_tid = self.tid.gen();
+        self.table_buffer.push(TableDecl {
+            id: input_tid,
+            name: None,
+            relation: rq::Relation {
+                kind: rq::RelationKind::Pipeline(transforms),
+                columns,
+            },
+        });
+
+        // the check aggregates an instance of the input
+        let check_input = self.create_a_table_instance(id, None, input_tid);
+        let check_cids = check_input.columns.iter().map(|(_, c)| *c).collect_vec();
+        self.redirect_mappings(zip(cids, check_cids.clone()).collect());
+
+        self.pipeline.push(Transform::From(check_input));
+
+        // a condition of a single row has to hold for all rows
+        let condition = if contains_aggregation(&condition) {
+            condition
+        } else {
+            pl::Expr {
+                span,
+                ..pl::Expr::new(pl::ExprKind::RqOperator {
+                    name: "std.all".to_string(),
+                    args: vec![condition],
+                })
+            }
+        };
+        let check = rq::Compute {
+            id: self.cid.gen(),
+            expr: rq::Expr {
+                kind: rq::ExprKind::Operator {
+                    name: "std.assert".to_string(),
+                    args: vec![self.lower_expr(condition)?],
+                },
+                span,
+            },
+            window: None,
+            is_aggregation: true,
+        };
+        let check_cid = check.id;
+        self.pipeline.push(Transform::Compute(check));
+        self.pipeline.push(Transform::Aggregate {
+            partition: vec![],
+            compute: vec![check_cid],
+        });
+        self.pipeline.push(Transform::Select(vec![check_cid]));
+
+        let check_tid = self.tid.gen();
+        self.table_buffer.push(TableDecl {
+            id: check_tid,
+            name: None,
+            relation: rq::Relation {
+                kind: rq::RelationKind::Pipeline(self.pipeline.drain(..).collect()),
+                columns: vec![RelationColumn::Single(Some("_assert".to_string()))],
+            },
+        });
+        let check = self.create_a_table_instance(id, None, check_tid);
+        let check_col = rq::Expr {
+            kind: rq::ExprKind::ColumnRef(check.columns[0].1),
+            span,
+        };
+
+        // the rest of the pipeline continues from another instance of the input
+        let input = self.create_a_table_instance(id, None, input_tid);
+        let input_cids = input.columns.iter().map(|(_, c)| *c);
+        self.redirect_mappings(zip(check_cids, input_cids).collect());
+
+        self.pipeline.push(Transform::From(input));
+        self.pipeline.push(Transform::Join {
+            side: JoinSide::Inner,
+            with: check,
+            filter: rq::new_binop(
+                check_col,
+                "std.eq",
+                rq::Expr {
+                    kind: rq::ExprKind::Literal(Literal::Integer(1)),
+                    span,
+                },
+            ),
+        });
+        Ok(())
+    }
+
     fn lower_range(&mut self, range: Range<Box<pl::Expr>>) -> Result<Range<rq::Expr>> {
         Ok(Range {
             start: range.start.map(|x| self.lower_expr(*x)).transpose()?,
@@ -902,6 +1141,13 @@ impl Lowerer {
                     })
                     .try_collect()?,
             ),
+            pl::ExprKind::RqOperator { name, .. } if name == "std.in_relation" => {
+                return Err(Error::new_simple(
+                    "`in` with a relation can only be a condition of `filter`, or one of its `&&` operands",
+                )
+                .push_hint("to keep rows that have no match in it, use `join side:anti`")
+                .with_span(span));
+            }
             pl::ExprKind::RqOperator { name, args } => {
                 let args = args.into_iter().map(|x| self.lower_expr(x)).try_collect()?;
 
@@ -924,6 +1170,23 @@ impl Lowerer {
                     .try_collect()?,
             ),
 
+            pl::ExprKind::Func(func) if func.args.len() < func.params.len() => {
+                let missing = &func.params[func.args.len()..];
+                let names = (missing.iter())
+                    .map(|p| format!("`{}`", p.name.split('.').last().unwrap_or(&p.name)))
+                    .join(", ");
+                return Err(Error::new_simple(format!(
+                    "Missing {} {names} to function `{}`",
+                    if missing.len() == 1 {
+                        "argument"
+                    } else {
+                        "arguments"
+                    },
+                    func.as_debug_name()
+                ))
+                .with_span(span));
+            }
+
             pl::ExprKind::FuncCall(_) | pl::ExprKind::Func(_) | pl::ExprKind::TransformCall(_) => {
                 log::debug!("cannot lower {expr:?}");
                 return Err(Error::new(Reason::Unexpected {
@@ -998,6 +1261,76 @@ fn str_lit(string: String) -> rq::Expr {
     }
 }
 
+/// Splits assigns of an aggregate into calls of value window functions
+/// (`first` and `last`) and the remaining expressions.
+fn split_value_functions(assigns: pl::Expr) -> (Vec<pl::Expr>, pl::Expr) {
+    let is_value_fn = |expr: &pl::Expr| {
+        matches!(
+            &expr.kind,
+            pl::ExprKind::RqOperator { name, .. } if name == "std.first" || name == "std.last"
+        )
+    };
+
+    match assigns.kind {
+        pl::ExprKind::Tuple(fields) => {
+            let (value_fns, fields) = fields.into_iter().partition(is_value_fn);
+            let assigns = pl::Expr {
+                kind: pl::ExprKind::Tuple(fields),
+                ..assigns
+            };
+            (value_fns, assigns)
+        }
+        kind => (vec![], pl::Expr { kind, ..assigns }),
+    }
+}
+
+type InRelation = (pl::Expr, pl::Expr, Option<Span>);
+
+/// Pulls `value | in relation` out of the conjunction of a filter, returning
+/// the value and the relation of each, and the rest of the filter.
+fn split_in_relations(filter: pl::Expr) -> (Vec<InRelation>, Option<pl::Expr>) {
+    match filter.kind {
+        pl::ExprKind::RqOperator { name, args } if name == "std.in_relation" => {
+            let [value, relation] = <[_; 2]>::try_from(args).unwrap();
+            (vec![(value, relation, filter.span)], None)
+        }
+        pl::ExprKind::RqOperator { name, args } if name == "std.and" => {
+            let [left, right] = <[_; 2]>::try_from(args).unwrap();
+            let (mut in_relations, left) = split_in_relations(left);
+            let (in_right, right) = split_in_relations(right);
+            in_relations.extend(in_right);
+
+            let rest = match (left, right) {
+                (Some(left), Some(right)) => Some(pl::Expr {
+                    kind: pl::ExprKind::RqOperator {
+                        name,
+                        args: vec![left, right],
+                    },
+                    ..filter
+                }),
+                (left, right) => left.or(right),
+            };
+            (in_relations, rest)
+        }
+        kind => (vec![], Some(pl::Expr { kind, ..filter })),
+    }
+}
+
+/// Returns the number of rows when a take has a negative bound, such as `take (-5)`.
+fn take_from_bottom(range: &Range<rq::Expr>) -> Option<i64> {
+    let end = range.end.as_ref()?.kind.as_literal()?.as_integer()?;
+
+    (range.start.is_none() && *end < 0).then_some(-end)
+}
+
+fn reverse_sort(sort: ColumnSort<CId>) -> ColumnSort<CId> {
+    let direction = match sort.direction {
+        SortDirection::Asc => SortDirection::Desc,
+        SortDirection::Desc => SortDirection::Asc,
+    };
+    ColumnSort { direction, ..sort }
+}
+
 fn validate_take_range(range: &Range<rq::Expr>, span: Option<Span>) -> Result<()> {
     fn bound_as_int(bound: &Option<rq::Expr>) -> Option<Option<&i64>> {
         bound
@@ -1021,7 +1354,9 @@ fn validate_take_range(range: &Range<rq::Expr>, span: Option<Span>) -> Result<()
     };
 
     let end_ok = if let Some(end) = end {
-        end.map(|e| *e >= 1).unwrap_or(false)
+        // `take 0` is allowed and produces no rows
+        end.map(|e| *e >= 1 || (*e == 0 && range.start.is_none()))
+            .unwrap_or(false)
     } else {
         true
     };
@@ -1039,6 +1374,31 @@ fn validate_take_range(range: &Range<rq::Expr>, span: Option<Span>) -> Result<()
     }
 }
 
+/// Whether the expression contains an aggregation (or any other function over
+/// the whole relation).
+fn contains_aggregation(expr: &pl::Expr) -> bool {
+    struct Finder {
+        found: bool,
+    }
+
+    impl PlFold for Finder {
+        fn fold_expr(&mut self, expr: pl::Expr) -> Result<pl::Expr> {
+            if expr.needs_window {
+                self.found = true;
+                return Ok(expr);
+            }
+            Ok(pl::Expr {
+                kind: self.fold_expr_kind(expr.kind)?,
+                ..expr
+            })
+        }
+    }
+
+    let mut finder = Finder { found: false };
+    finder.fold_expr(expr.clone()).unwrap();
+    finder.found
+}
+
 #[derive(Default)]
 struct TableExtractor {
     path: Vec<String>,
@@ -1134,8 +1494,14 @@ impl PlFold for TableDepsCollector {
                 pl::ExprKind::TransformCall(self.fold_transform_call(tc)?)
             }
             pl::ExprKind::Func(func) => pl::ExprKind::Func(Box::new(self.fold_func(*func)?)),
+            // operators may contain relations, such as `x | in (from y | select {z})`
+            pl::ExprKind::RqOperator { name, args } => pl::ExprKind::RqOperator {
+                name,
+                args: self.fold_exprs(args)?,
+            },
 
-            // optimization: don't recurse into anything else than TransformCalls and Func
+            // optimization: don't recurse into anything else than TransformCalls, Func
+            // and operators
             _ => expr.kind,
         };
         Ok(expr)
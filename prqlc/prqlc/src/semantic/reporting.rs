@@ -252,7 +252,9 @@ impl PlFold for FrameCollector {
                     match *tc.kind {
                         pl::TransformKind::Derive { assigns: ref e }
                         | pl::TransformKind::Select { assigns: ref e }
+                        | pl::TransformKind::Rename { assigns: ref e }
                         | pl::TransformKind::Filter { filter: ref e }
+                        | pl::TransformKind::Assert { condition: ref e }
                         | pl::TransformKind::Append(ref e)
                         | pl::TransformKind::Loop(ref e)
                         | pl::TransformKind::Group {
@@ -277,7 +279,7 @@ impl PlFold for FrameCollector {
                             tcc.push(with.id.unwrap());
                             tcc.push(filter.id.unwrap());
                         }
-                        pl::TransformKind::Take { ref range } => {
+                        pl::TransformKind::Take { ref range, .. } => {
                             if let Some(e) = &range.start {
                                 tcc.push(e.id.unwrap());
                             }
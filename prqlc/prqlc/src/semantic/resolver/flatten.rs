@@ -19,6 +19,11 @@ pub struct Flattener {
 
     sort_undone: bool,
 
+    /// Set once the inner pipeline of a group has been aggregated.
+    /// There is a single row per group after that, so a sort there orders
+    /// the groups themselves and is kept.
+    aggregated: bool,
+
     /// Group affects transforms in it's inner pipeline.
     /// This means that this field has to be set before folding inner pipeline,
     /// and unset after the folding.
@@ -69,7 +74,7 @@ impl PlFold for Flattener {
 
                         self.sort.clone_from(&by);
 
-                        if self.sort_undone {
+                        if self.sort_undone && !self.aggregated {
                             return Ok(input);
                         } else {
                             (input, TransformKind::Sort { by })
@@ -79,6 +84,8 @@ impl PlFold for Flattener {
                         let sort_undone = self.sort_undone;
                         self.sort_undone = true;
 
+                        let aggregated = self.aggregated;
+
                         let input = self.fold_expr(*t.input)?;
 
                         let pipeline = pipeline.kind.into_func().unwrap();
@@ -89,13 +96,17 @@ impl PlFold for Flattener {
                         self.replace_map.insert(param_id, input);
                         self.partition = Some(by);
                         self.sort.clear();
+                        self.aggregated = false;
 
                         let pipeline = self.fold_expr(*pipeline.body)?;
 
                         self.replace_map.remove(&param_id);
                         self.partition = None;
-                        self.sort.clear();
+                        if !self.aggregated {
+                            self.sort.clear();
+                        }
                         self.sort_undone = sort_undone;
+                        self.aggregated = aggregated;
 
                         return Ok(Expr {
                             ty: expr.ty,
@@ -128,6 +139,11 @@ impl PlFold for Flattener {
                             ..pipeline
                         });
                     }
+                    kind @ TransformKind::Aggregate { .. } => {
+                        let input = self.fold_expr(*t.input)?;
+                        self.aggregated = self.partition.is_some();
+                        (input, fold_transform_kind(self, kind)?)
+                    }
                     kind => (self.fold_expr(*t.input)?, fold_transform_kind(self, kind)?),
                 };
 
@@ -10,7 +10,7 @@ use crate::pr::{Ty, TyFunc, TyKind};
 use crate::semantic::resolver::types;
 use crate::semantic::{NS_GENERIC, NS_PARAM, NS_THAT, NS_THIS};
 use crate::Result;
-use crate::{Error, Span, WithErrorInfo};
+use crate::{Error, Reason, Span, WithErrorInfo};
 
 impl Resolver<'_> {
     pub fn fold_function(
@@ -212,12 +212,13 @@ impl Resolver<'_> {
             closure.args.push(arg);
             closure.params.insert(closure.args.len() - 1, param);
         }
-        if let Some((name, _)) = named_args.into_iter().next() {
+        if let Some((name, arg)) = named_args.into_iter().next() {
             // TODO: report all remaining named_args as separate errors
             return Err(Error::new_simple(format!(
-                "unknown named argument `{name}` to closure {:?}",
-                closure.name_hint
-            )));
+                "Unknown named argument `{name}` to function `{}`",
+                closure.as_debug_name()
+            ))
+            .with_span(arg.span));
         }
 
         // positional
@@ -295,9 +296,16 @@ impl Resolver<'_> {
                     // if this is a tuple, resolve elements separately,
                     // so they can be added to scope, before resolving subsequent elements.
 
+                    let aliases = fields.iter().map(|f| f.alias.clone()).collect_vec();
+
                     let mut fields_new = Vec::with_capacity(fields.len());
-                    for field in fields {
-                        let field = self.fold_within_namespace(field, &param.name)?;
+                    for (position, field) in fields.into_iter().enumerate() {
+                        let later = aliases[position + 1..].iter().flatten();
+                        let references = find_references(&field, later);
+
+                        let field = self
+                            .fold_within_namespace(field, &param.name)
+                            .map_err(|err| into_forward_reference_error(err, references))?;
 
                         // add aliased columns into scope
                         if let Some(alias) = field.alias.clone() {
@@ -495,3 +503,56 @@ pub fn expr_of_func(func: Box<Func>, span: Option<Span>) -> Box<Expr> {
         ..Expr::new(ExprKind::Func(func))
     })
 }
+
+/// References to any of the `names` within the expression, with their spans.
+fn find_references<'a>(
+    expr: &Expr,
+    names: impl Iterator<Item = &'a String>,
+) -> Vec<(String, Span)> {
+    struct Finder<'a> {
+        names: Vec<&'a String>,
+        found: Vec<(String, Span)>,
+    }
+
+    impl PlFold for Finder<'_> {
+        fn fold_expr(&mut self, expr: Expr) -> Result<Expr> {
+            if let (ExprKind::Ident(ident), Some(span)) = (&expr.kind, expr.span) {
+                if ident.path.is_empty() && self.names.contains(&&ident.name) {
+                    self.found.push((ident.name.clone(), span));
+                }
+            }
+            Ok(Expr {
+                kind: self.fold_expr_kind(expr.kind)?,
+                ..expr
+            })
+        }
+    }
+
+    let mut finder = Finder {
+        names: names.collect(),
+        found: Vec::new(),
+    };
+    if finder.names.is_empty() {
+        return Vec::new();
+    }
+    finder.fold_expr(expr.clone()).unwrap();
+    finder.found
+}
+
+/// When a name could not be resolved because it is only defined later in the
+/// same tuple, explain that instead.
+fn into_forward_reference_error(err: Error, references: Vec<(String, Span)>) -> Error {
+    let Some((name, _)) = references
+        .into_iter()
+        .find(|(_, span)| Some(*span) == err.span)
+    else {
+        return err;
+    };
+    Error {
+        reason: Reason::Simple(format!("`{name}` is used before it is defined")),
+        hints: vec![format!(
+            "move `{name}` before its use, or define it in an earlier transform"
+        )],
+        ..err
+    }
+}
@@ -47,6 +47,9 @@ impl Resolver<'_> {
                 let mut available_names = Vec::new();
                 available_names.extend(self.collect_columns_in_module(NS_THIS));
                 available_names.extend(self.collect_columns_in_module(NS_THAT));
+                if let Some(similar) = most_similar_name(&ident.name, &available_names) {
+                    res = res.push_hint(format!("did you mean `{similar}`?"));
+                }
                 if !available_names.is_empty() {
                     let available_names = available_names.iter().map(Ident::to_string).join(", ");
                     res = res.push_hint(format!("available columns: {available_names}"));
@@ -239,6 +242,34 @@ impl Resolver<'_> {
     }
 }
 
+/// Finds the column that is closest to a misspelled name, if any is close enough.
+fn most_similar_name<'a>(name: &str, available: &'a [Ident]) -> Option<&'a Ident> {
+    // a third of the name may be misspelled, so short names get no suggestion
+    let max_distance = name.chars().count() / 3;
+    available
+        .iter()
+        .map(|ident| (edit_distance(name, &ident.name), ident))
+        .filter(|(distance, _)| (1..=max_distance).contains(distance))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, ident)| ident)
+}
+
+/// Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect_vec();
+    let mut row = (0..=b.len()).collect_vec();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev + usize::from(ca != *cb);
+            prev = row[j + 1];
+            row[j + 1] = substitution.min(prev + 1).min(row[j] + 1);
+        }
+    }
+    row[b.len()]
+}
+
 fn ambiguous_error(idents: HashSet<Ident>, replace_name: Option<&String>) -> Error {
     let all_this = idents.iter().all(|d| d.starts_with_part(NS_THIS));
 
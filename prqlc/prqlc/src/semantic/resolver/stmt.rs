@@ -1,15 +1,17 @@
 use std::collections::HashMap;
 
+use itertools::Itertools;
+
 use crate::ir::decl::{Decl, DeclKind, Module, TableDecl, TableExpr};
 use crate::ir::pl::*;
 use crate::pr::{Ty, TyKind, TyTupleField};
 use crate::Result;
-use crate::WithErrorInfo;
+use crate::{Error, WithErrorInfo};
 
 impl super::Resolver<'_> {
     // entry point to the resolver
     pub fn fold_statements(&mut self, stmts: Vec<Stmt>) -> Result<()> {
-        for mut stmt in stmts {
+        for mut stmt in order_by_relation_refs(stmts)? {
             stmt.id = Some(self.id.gen());
             if let Some(span) = stmt.span {
                 self.root_mod.span_map.insert(stmt.id.unwrap(), span);
@@ -71,6 +73,12 @@ impl super::Resolver<'_> {
                     continue;
                 }
                 StmtKind::ImportDef(target) => {
+                    // `import lib` within the module that contains `lib` would
+                    // only replace `lib` with a reference to itself
+                    if target.name == ident {
+                        continue;
+                    }
+
                     let decl = Decl {
                         declared_at: stmt.id,
                         kind: DeclKind::Import(target.name),
@@ -157,3 +165,123 @@ fn prepare_expr_decl(value: Box<Expr>) -> DeclKind {
         _ => DeclKind::Expr(value),
     }
 }
+
+/// Transforms whose first positional argument is a relation that is looked up
+/// in the database module, unless it's declared in the query.
+const RELATION_ARGS: &[&str] = &["from", "join", "append", "intersect", "remove"];
+
+/// Orders the statements of a module so that each one comes after the
+/// relations it reads from, which allows reading from a relation that is
+/// declared further down. The order is kept where it already allows this.
+///
+/// A relation that reads from itself is reading from the database table of
+/// the same name, so this is not a circular reference.
+fn order_by_relation_refs(stmts: Vec<Stmt>) -> Result<Vec<Stmt>> {
+    let index: HashMap<&str, usize> = (stmts.iter().enumerate())
+        .map(|(i, stmt)| (stmt.name(), i))
+        .collect();
+
+    let deps: Vec<Vec<usize>> = (stmts.iter().enumerate())
+        .map(|(i, stmt)| {
+            RelationRefCollector::collect(stmt)
+                .iter()
+                .filter_map(|name| index.get(name.as_str()).copied())
+                .filter(|dep| *dep != i)
+                .collect()
+        })
+        .collect();
+
+    let mut sort = RelationRefSort {
+        deps: &deps,
+        visiting: Vec::new(),
+        done: vec![false; stmts.len()],
+        order: Vec::with_capacity(stmts.len()),
+    };
+    for i in 0..stmts.len() {
+        if let Err(cycle) = sort.visit(i) {
+            let path = (cycle.iter().chain(cycle.first()))
+                .map(|i| format!("`{}`", stmts[*i].name()))
+                .join(" -> ");
+            return Err(
+                Error::new_simple(format!("Circular reference between relations: {path}"))
+                    .with_span(stmts[cycle[0]].span),
+            );
+        }
+    }
+
+    let mut stmts = stmts.into_iter().map(Some).collect_vec();
+    Ok(sort
+        .order
+        .into_iter()
+        .map(|i| stmts[i].take().unwrap())
+        .collect())
+}
+
+struct RelationRefSort<'a> {
+    deps: &'a [Vec<usize>],
+    visiting: Vec<usize>,
+    done: Vec<bool>,
+    order: Vec<usize>,
+}
+
+impl RelationRefSort<'_> {
+    /// Returns the statements of a cycle, if there is one.
+    fn visit(&mut self, i: usize) -> Result<(), Vec<usize>> {
+        if self.done[i] {
+            return Ok(());
+        }
+        if let Some(start) = self.visiting.iter().position(|v| *v == i) {
+            return Err(self.visiting[start..].to_vec());
+        }
+
+        self.visiting.push(i);
+        for dep in &self.deps[i] {
+            self.visit(*dep)?;
+        }
+        self.visiting.pop();
+
+        self.done[i] = true;
+        self.order.push(i);
+        Ok(())
+    }
+}
+
+/// Collects the names of relations that a variable reads from, without
+/// looking into functions.
+#[derive(Default)]
+struct RelationRefCollector {
+    names: Vec<String>,
+}
+
+impl RelationRefCollector {
+    fn collect(stmt: &Stmt) -> Vec<String> {
+        let mut c = RelationRefCollector::default();
+        if let StmtKind::VarDef(VarDef {
+            value: Some(value), ..
+        }) = &stmt.kind
+        {
+            if !value.kind.is_func() {
+                c.fold_expr(*value.clone()).unwrap();
+            }
+        }
+        c.names
+    }
+}
+
+impl PlFold for RelationRefCollector {
+    fn fold_func_call(&mut self, call: FuncCall) -> Result<FuncCall> {
+        if let (ExprKind::Ident(name), Some(arg)) = (&call.name.kind, call.args.first()) {
+            if let (true, ExprKind::Ident(relation)) =
+                (RELATION_ARGS.contains(&name.name.as_str()), &arg.kind)
+            {
+                let first = relation.path.first().unwrap_or(&relation.name);
+                self.names.push(first.clone());
+            }
+        }
+        fold_func_call(self, call)
+    }
+
+    fn fold_func(&mut self, func: Func) -> Result<Func> {
+        Ok(func)
+    }
+}
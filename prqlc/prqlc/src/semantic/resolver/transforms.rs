@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::iter::zip;
 
 use itertools::Itertools;
@@ -9,7 +9,7 @@ use super::Resolver;
 use crate::ir::decl::{Decl, DeclKind, Module};
 use crate::ir::generic::{SortDirection, WindowKind};
 use crate::ir::pl::*;
-use crate::pr::{Ty, TyKind, TyTupleField};
+use crate::pr::{Span, Ty, TyKind, TyTupleField};
 use crate::semantic::ast_expand::{restrict_null_literal, try_restrict_range};
 use crate::semantic::resolver::functions::expr_of_func;
 use crate::semantic::{write_pl, NS_PARAM, NS_THIS};
@@ -28,16 +28,44 @@ impl Resolver<'_> {
                 let assigns = Box::new(self.coerce_into_tuple(assigns)?);
                 (TransformKind::Select { assigns }, tbl)
             }
+            "rename" => {
+                let [assigns, tbl] = unpack::<2>(func.args);
+
+                let assigns = self.coerce_into_tuple(assigns)?;
+                for field in assigns.kind.as_tuple().unwrap() {
+                    if field.alias.is_none() || !field.kind.is_ident() {
+                        return Err(Error::new_simple(
+                            "`rename` expects columns with new names, such as `new_name = old_name`",
+                        )
+                        .with_span(field.span));
+                    }
+                }
+                (
+                    TransformKind::Rename {
+                        assigns: Box::new(assigns),
+                    },
+                    tbl,
+                )
+            }
             "filter" => {
                 let [filter, tbl] = unpack::<2>(func.args);
 
                 let filter = Box::new(filter);
                 (TransformKind::Filter { filter }, tbl)
             }
+            "assert" => {
+                let [condition, tbl] = unpack::<2>(func.args);
+
+                let condition = Box::new(condition);
+                (TransformKind::Assert { condition }, tbl)
+            }
             "derive" => {
                 let [assigns, tbl] = unpack::<2>(func.args);
 
                 let assigns = Box::new(self.coerce_into_tuple(assigns)?);
+                if let Some(lineage) = &tbl.lineage {
+                    validate_derived_names(&assigns, lineage)?;
+                }
                 (TransformKind::Derive { assigns }, tbl)
             }
             "aggregate" => {
@@ -69,30 +97,73 @@ impl Resolver<'_> {
                 (TransformKind::Sort { by }, tbl)
             }
             "take" => {
-                let [expr, tbl] = unpack::<2>(func.args);
+                let [rows, expr, tbl] = unpack::<3>(func.args);
+
+                let rows_span = rows.span;
+                let rows = rows.try_cast(ExprKind::into_ident, Some("rows"), "ident")?;
+                let from_bottom = match rows.to_string().as_str() {
+                    "any" => None,
+                    "first" => Some(false),
+                    "last" => Some(true),
+                    _ => {
+                        return Err(Error::new(Reason::Expected {
+                            who: Some("`rows`".to_string()),
+                            expected: "any, first or last".to_string(),
+                            found: rows.to_string(),
+                        })
+                        .with_span(rows_span));
+                    }
+                };
 
-                let range = if let ExprKind::Literal(Literal::Integer(n)) = expr.kind {
-                    range_from_ints(None, Some(n))
-                } else {
-                    match try_restrict_range(expr) {
-                        Ok((start, end)) => Range {
-                            start: restrict_null_literal(start).map(Box::new),
-                            end: restrict_null_literal(end).map(Box::new),
+                if let Some(from_bottom) = from_bottom {
+                    let ExprKind::Literal(Literal::Integer(n @ 0..)) = expr.kind else {
+                        return Err(Error::new(Reason::Expected {
+                            who: Some(format!("`take {rows}`")),
+                            expected: "a non-negative int literal".to_string(),
+                            found: write_pl(expr.clone()),
+                        })
+                        .with_span(expr.span));
+                    };
+                    let n = if from_bottom { -n } else { n };
+
+                    let range = range_from_ints(None, Some(n), expr.span);
+                    (
+                        TransformKind::Take {
+                            range,
+                            sorted: true,
                         },
-                        Err(expr) => {
-                            return Err(Error::new(Reason::Expected {
-                                who: Some("`take`".to_string()),
-                                expected: "int or range".to_string(),
-                                found: write_pl(expr.clone()),
-                            })
-                            // Possibly this should refer to the item after the `take` where
-                            // one exists?
-                            .with_span(expr.span));
+                        tbl,
+                    )
+                } else {
+                    let range = if let ExprKind::Literal(Literal::Integer(n)) = expr.kind {
+                        range_from_ints(None, Some(n), expr.span)
+                    } else {
+                        match try_restrict_range(expr) {
+                            Ok((start, end)) => Range {
+                                start: restrict_null_literal(start).map(Box::new),
+                                end: restrict_null_literal(end).map(Box::new),
+                            },
+                            Err(expr) => {
+                                return Err(Error::new(Reason::Expected {
+                                    who: Some("`take`".to_string()),
+                                    expected: "int or range".to_string(),
+                                    found: write_pl(expr.clone()),
+                                })
+                                // Possibly this should refer to the item after the `take` where
+                                // one exists?
+                                .with_span(expr.span));
+                            }
                         }
-                    }
-                };
+                    };
 
-                (TransformKind::Take { range }, tbl)
+                    (
+                        TransformKind::Take {
+                            range,
+                            sorted: false,
+                        },
+                        tbl,
+                    )
+                }
             }
             "join" => {
                 let [side, with, filter, tbl] = unpack::<4>(func.args);
@@ -109,6 +180,8 @@ impl Resolver<'_> {
                         "left" => JoinSide::Left,
                         "right" => JoinSide::Right,
                         "full" => JoinSide::Full,
+                        "semi" => JoinSide::Semi,
+                        "anti" => JoinSide::Anti,
 
                         _ => {
                             // if that fails, fold the ident and try treating the result as a literal
@@ -125,11 +198,14 @@ impl Resolver<'_> {
                                 "\"left\"" => JoinSide::Left,
                                 "\"right\"" => JoinSide::Right,
                                 "\"full\"" => JoinSide::Full,
+                                "\"semi\"" => JoinSide::Semi,
+                                "\"anti\"" => JoinSide::Anti,
 
                                 _ => {
                                     return Err(Error::new(Reason::Expected {
                                         who: Some("`side`".to_string()),
-                                        expected: "inner, left, right or full".to_string(),
+                                        expected: "inner, left, right, full, semi or anti"
+                                            .to_string(),
                                         found: folded.to_string(),
                                     })
                                     .with_span(span))
@@ -251,6 +327,15 @@ impl Resolver<'_> {
 
                 let [pattern, value] = unpack::<2>(func.args);
 
+                // relations are arrays of tuples, but expressions can't contain
+                // subqueries, so this is lowered into a semi join of `filter`
+                if pattern.ty.as_ref().map_or(false, |x| x.is_relation()) {
+                    return Ok(Expr::new(ExprKind::RqOperator {
+                        name: "std.in_relation".to_string(),
+                        args: vec![value, pattern],
+                    }));
+                }
+
                 if pattern.ty.as_ref().map_or(false, |x| x.kind.is_array()) {
                     return Ok(Expr::new(ExprKind::RqOperator {
                         name: "std.array_in".to_string(),
@@ -283,6 +368,43 @@ impl Resolver<'_> {
                 .with_span(pattern.span));
             }
 
+            "overlaps" => {
+                // yes, this is not a transform, but this is the most appropriate place for it
+
+                let [range, value] = unpack::<2>(func.args);
+                let range_span = range.span;
+
+                let (start, end) = match try_restrict_range(range) {
+                    Ok((start, end)) => (restrict_null_literal(start), restrict_null_literal(end)),
+                    Err(expr) => {
+                        return Err(Error::new(Reason::Expected {
+                            who: Some("std.overlaps".to_string()),
+                            expected: "a range".to_string(),
+                            found: write_pl(expr.clone()),
+                        })
+                        .with_span(expr.span));
+                    }
+                };
+                let (Some(start), Some(end)) = (start, end) else {
+                    return Err(
+                        Error::new_simple("`overlaps` requires a range with both bounds")
+                            .with_span(range_span),
+                    );
+                };
+
+                // a tuple of two values is a period, anything else is a range value
+                let (name, mut args) = match value.kind {
+                    ExprKind::Tuple(fields) if fields.len() == 2 => ("std.period_overlaps", fields),
+                    kind => ("std.range_overlaps", vec![Expr { kind, ..value }]),
+                };
+                args.extend([start, end]);
+
+                return Ok(Expr::new(ExprKind::RqOperator {
+                    name: name.to_string(),
+                    args,
+                }));
+            }
+
             "tuple_every" => {
                 // yes, this is not a transform, but this is the most appropriate place for it
 
@@ -431,6 +553,7 @@ impl Resolver<'_> {
                 // But because these functions don't *really* need an arg, we can just pass
                 // a null instead.
                 return Ok(Expr {
+                    ty: func.return_ty,
                     needs_window,
                     ..Expr::new(ExprKind::RqOperator {
                         name: format!("std.{internal_name}"),
@@ -439,6 +562,24 @@ impl Resolver<'_> {
                 });
             }
 
+            "count_distinct" => {
+                let [column] = unpack::<1>(func.args);
+
+                // a tuple counts distinct combinations of its fields
+                let args = match column.kind {
+                    ExprKind::Tuple(fields) => fields,
+                    kind => vec![Expr { kind, ..column }],
+                };
+                return Ok(Expr {
+                    ty: func.return_ty,
+                    needs_window,
+                    ..Expr::new(ExprKind::RqOperator {
+                        name: "std.count_distinct".to_string(),
+                        args,
+                    })
+                });
+            }
+
             _ => {
                 return Err(
                     Error::new_simple(format!("unknown operator {internal_name}"))
@@ -511,14 +652,40 @@ impl Resolver<'_> {
                     [input, derived].concat(),
                 ))))))
             }
+            TransformKind::Rename { assigns } => {
+                let input = transform_call.input.ty.clone().unwrap();
+                let mut input = input.into_relation().unwrap();
+
+                // the renamed fields are moved to the end, which is not always
+                // where lineage puts them, but types don't track positions
+                let renamed: HashSet<_> = (assigns.kind.as_tuple().unwrap().iter())
+                    .filter_map(|f| f.kind.as_ident())
+                    .map(|i| i.name.as_str())
+                    .collect();
+                input.retain(|f| {
+                    !matches!(f, TyTupleField::Single(Some(name), _) if renamed.contains(name.as_str()))
+                });
+
+                let renames = assigns.ty.clone().unwrap();
+                let renames = renames.kind.into_tuple().unwrap();
+
+                Some(Ty::new(TyKind::Array(Box::new(Ty::new(ty_tuple_kind(
+                    [input, renames].concat(),
+                ))))))
+            }
             TransformKind::Aggregate { assigns } => {
                 let tuple = assigns.ty.clone().unwrap();
 
                 Some(Ty::new(TyKind::Array(Box::new(tuple))))
             }
             TransformKind::Filter { .. }
+            | TransformKind::Assert { .. }
             | TransformKind::Sort { .. }
             | TransformKind::Take { .. } => transform_call.input.ty.clone(),
+            TransformKind::Join {
+                side: JoinSide::Semi | JoinSide::Anti,
+                ..
+            } => transform_call.input.ty.clone(),
             TransformKind::Join { with, .. } => {
                 let input = transform_call.input.ty.clone().unwrap();
                 let input = input.into_relation().unwrap();
@@ -559,6 +726,63 @@ impl Resolver<'_> {
     }
 }
 
+/// Checks that a `derive` doesn't introduce a column whose name is already in use,
+/// which would silently shadow the existing column.
+///
+/// Redefining a column in terms of itself (`derive a = a + 1`) is allowed, as is
+/// replacing columns with `select`.
+fn validate_derived_names(assigns: &Expr, input: &Lineage) -> Result<()> {
+    let mut existing: HashSet<&str> = (input.columns.iter())
+        .filter_map(|col| match col {
+            LineageColumn::Single { name: Some(n), .. } => Some(n.name.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    for field in assigns.kind.as_tuple().into_iter().flatten() {
+        let Some(name) = &field.alias else {
+            continue;
+        };
+
+        if !existing.insert(name) && !references_name(field, name) {
+            return Err(
+                Error::new_simple(format!("column `{name}` is already defined"))
+                    .push_hint("use a different name or replace the column with `select`")
+                    .with_span(field.span),
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Does the expression contain a reference to a column with this name?
+fn references_name(expr: &Expr, name: &str) -> bool {
+    struct Finder<'a> {
+        name: &'a str,
+        found: bool,
+    }
+
+    impl PlFold for Finder<'_> {
+        fn fold_expr(&mut self, expr: Expr) -> Result<Expr> {
+            if let ExprKind::Ident(ident) = &expr.kind {
+                self.found |= ident.name == self.name;
+            }
+            Ok(Expr {
+                kind: self.fold_expr_kind(expr.kind)?,
+                ..expr
+            })
+        }
+    }
+
+    let mut finder = Finder { name, found: false };
+    let expr = Expr {
+        alias: None,
+        ..expr.clone()
+    };
+    finder.fold_expr(expr).unwrap();
+    finder.found
+}
+
 fn range_is_empty(range: &(Option<i64>, Option<i64>)) -> bool {
     match (&range.0, &range.1) {
         (Some(s), Some(e)) => s > e,
@@ -566,10 +790,16 @@ fn range_is_empty(range: &(Option<i64>, Option<i64>)) -> bool {
     }
 }
 
-fn range_from_ints(start: Option<i64>, end: Option<i64>) -> Range {
-    let start = start.map(|x| Box::new(Expr::new(ExprKind::Literal(Literal::Integer(x)))));
-    let end = end.map(|x| Box::new(Expr::new(ExprKind::Literal(Literal::Integer(x)))));
-    Range { start, end }
+fn range_from_ints(start: Option<i64>, end: Option<i64>, span: Option<Span>) -> Range {
+    let new_int = |x| {
+        let mut expr = Expr::new(ExprKind::Literal(Literal::Integer(x)));
+        expr.span = span;
+        Box::new(expr)
+    };
+    Range {
+        start: start.map(new_int),
+        end: end.map(new_int),
+    }
 }
 
 fn into_literal_range(range: (Expr, Expr)) -> Result<(Option<i64>, Option<i64>)> {
@@ -675,6 +905,12 @@ impl TransformCall {
                 lineage.apply_assigns(assigns, false);
                 lineage
             }
+            Rename { assigns } => {
+                let mut lineage = lineage_or_default(&self.input)?;
+
+                lineage.apply_renames(assigns);
+                lineage
+            }
             Group { pipeline, by, .. } => {
                 let mut lineage = lineage_or_default(&self.input)?;
                 lineage.clear();
@@ -702,6 +938,10 @@ impl TransformCall {
                 lineage.apply_assigns(assigns, false);
                 lineage
             }
+            Join {
+                side: JoinSide::Semi | JoinSide::Anti,
+                ..
+            } => lineage_or_default(&self.input)?,
             Join { with, .. } => {
                 let left = lineage_or_default(&self.input)?;
                 let right = lineage_or_default(with)?;
@@ -713,7 +953,9 @@ impl TransformCall {
                 append(top, bottom)?
             }
             Loop(_) => lineage_or_default(&self.input)?,
-            Sort { .. } | Filter { .. } | Take { .. } => lineage_or_default(&self.input)?,
+            Sort { .. } | Filter { .. } | Assert { .. } | Take { .. } => {
+                lineage_or_default(&self.input)?
+            }
         })
     }
 }
@@ -950,6 +1192,60 @@ impl Lineage {
         });
     }
 
+    /// Renames columns in place. A column that is not known by name, but is
+    /// a part of a wildcard, is excluded from the wildcard and appended.
+    pub fn apply_renames(&mut self, assigns: &Expr) {
+        let ExprKind::Tuple(fields) = &assigns.kind else {
+            return;
+        };
+
+        for field in fields {
+            let mut renamed = Lineage {
+                inputs: self.inputs.clone(),
+                ..Default::default()
+            };
+            renamed.apply_assign(
+                &Expr {
+                    alias: None,
+                    ..field.clone()
+                },
+                true,
+            );
+            let Some(LineageColumn::Single {
+                name: Some(old_name),
+                target_id,
+                target_name,
+            }) = renamed.columns.pop()
+            else {
+                continue;
+            };
+
+            let new = LineageColumn::Single {
+                name: field.alias.as_ref().map(Ident::from_name),
+                target_id: field.id.unwrap(),
+                target_name: None,
+            };
+
+            let position = self.columns.iter().position(|c| match c {
+                LineageColumn::Single { name, .. } => name.as_ref() == Some(&old_name),
+                LineageColumn::All { .. } => false,
+            });
+            if let Some(position) = position {
+                self.columns[position] = new;
+                continue;
+            }
+
+            for col in &mut self.columns {
+                if let LineageColumn::All { input_id, except } = col {
+                    if *input_id == target_id {
+                        except.extend(target_name.clone());
+                    }
+                }
+            }
+            self.columns.push(new);
+        }
+    }
+
     pub fn find_input_by_name(&self, input_name: &str) -> Option<&LineageInput> {
         self.inputs.iter().find(|i| i.name == input_name)
     }
@@ -1107,7 +1403,7 @@ mod tests {
         group invoice_no (
             take 1
         )
-        ").unwrap(), @r"
+        ").unwrap(), @r#"
         def:
           version: ~
           other: {}
@@ -1142,7 +1438,7 @@ mod tests {
                       kind:
                         Literal:
                           Integer: 1
-                      span: ~
+                      span: "1:94-95"
                   partition:
                     - 0
                   sort: []
@@ -1150,7 +1446,7 @@ mod tests {
                   - 0
           columns:
             - Single: invoice_no
-        ");
+        "#);
 
         // oops, two arguments #339
         let result = parse_resolve_and_lower(
@@ -97,6 +97,24 @@ impl Dialect {
     pub fn names() -> &'static [&'static str] {
         Dialect::VARIANTS
     }
+
+    /// The dialect of [sqlparser] that parses SQL of this dialect.
+    pub(crate) fn parser_dialect(&self) -> Box<dyn sqlparser::dialect::Dialect> {
+        use sqlparser::dialect as sp;
+
+        match self {
+            Dialect::Ansi => Box::new(sp::AnsiDialect {}),
+            Dialect::BigQuery => Box::new(sp::BigQueryDialect {}),
+            Dialect::ClickHouse => Box::new(sp::ClickHouseDialect {}),
+            Dialect::DuckDb => Box::new(sp::DuckDbDialect {}),
+            Dialect::Generic => Box::new(sp::GenericDialect {}),
+            Dialect::GlareDb | Dialect::Postgres => Box::new(sp::PostgreSqlDialect {}),
+            Dialect::MsSql => Box::new(sp::MsSqlDialect {}),
+            Dialect::MySql => Box::new(sp::MySqlDialect {}),
+            Dialect::SQLite => Box::new(sp::SQLiteDialect {}),
+            Dialect::Snowflake => Box::new(sp::SnowflakeDialect {}),
+        }
+    }
 }
 
 pub enum SupportLevel {
@@ -105,6 +123,60 @@ pub enum SupportLevel {
     Nascent,
 }
 
+/// Which identifiers are quoted in the generated SQL.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    Serialize,
+    Deserialize,
+    strum::Display,
+    strum::EnumString,
+    strum::VariantNames,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum IdentQuoting {
+    /// Quote identifiers that are keywords of the dialect, or that are not
+    /// lower-case, which most databases would otherwise fold to another case.
+    #[default]
+    WhenNeeded,
+    /// Quote all identifiers.
+    Always,
+    /// Never quote identifiers. Names that need quoting produce invalid SQL.
+    Never,
+}
+
+/// How the case of identifiers is changed in the generated SQL.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    Serialize,
+    Deserialize,
+    strum::Display,
+    strum::EnumString,
+    strum::VariantNames,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum IdentCase {
+    /// Keep names as they are written in the query.
+    #[default]
+    Preserve,
+    /// Lower-case all names.
+    Lower,
+    /// Upper-case all names. Upper-case names aren't quoted for this reason
+    /// alone, as in Snowflake, which folds unquoted names to upper case.
+    Upper,
+}
+
 #[derive(Debug)]
 pub struct GenericDialect;
 #[derive(Debug)]
@@ -131,6 +203,28 @@ pub(super) enum ColumnExclude {
     Except,
 }
 
+/// Units of `std.date.diff` and `std.date.add`.
+pub(super) const DATE_UNITS: &[&str] =
+    &["year", "month", "week", "day", "hour", "minute", "second"];
+
+/// How `COUNT(DISTINCT ...)` is applied to multiple columns.
+pub(super) enum CountDistinctColumns {
+    /// `COUNT(DISTINCT (a, b))`
+    Row,
+    /// `COUNT(DISTINCT a, b)`
+    List,
+}
+
+/// How null-safe comparisons are written.
+pub(super) enum DistinctFrom {
+    /// `a IS DISTINCT FROM b`
+    Standard,
+    /// `NOT a <=> b`
+    Spaceship,
+    /// `a IS NOT b`
+    Is,
+}
+
 pub(super) trait DialectHandler: Any + Debug {
     fn use_fetch(&self) -> bool {
         false
@@ -144,6 +238,12 @@ pub(super) trait DialectHandler: Any + Debug {
         None
     }
 
+    /// Support for renaming columns of a star, as `* RENAME (a AS b)`.
+    /// When not supported, renamed columns are excluded from the star.
+    fn supports_star_rename(&self) -> bool {
+        false
+    }
+
     /// Support for DISTINCT in set ops (UNION DISTINCT, INTERSECT DISTINCT)
     /// When not supported we fallback to implicit DISTINCT.
     fn set_ops_distinct(&self) -> bool {
@@ -181,6 +281,17 @@ pub(super) trait DialectHandler: Any + Debug {
         false
     }
 
+    /// Support for GROUP BY ALL, which groups by all columns that are not aggregated
+    fn supports_group_by_all(&self) -> bool {
+        false
+    }
+
+    /// Whether rows can be filtered by window functions with a QUALIFY clause,
+    /// without wrapping the query in a CTE.
+    fn supports_qualify(&self) -> bool {
+        false
+    }
+
     /// Get the date format for the given dialect
     /// PRQL uses the same format as `chrono` crate
     /// (see https://docs.rs/chrono/latest/chrono/format/strftime/index.html)
@@ -197,9 +308,46 @@ pub(super) trait DialectHandler: Any + Debug {
         ))
     }
 
+    /// Units of [DATE_UNITS] that `std.date.diff` supports.
+    fn date_diff_units(&self) -> &'static [&'static str] {
+        DATE_UNITS
+    }
+
+    /// Translate the unit of `std.date.diff` or `std.date.add`, which is one of
+    /// [DATE_UNITS].
+    fn translate_date_unit(&self, unit: &str) -> String {
+        unit.to_uppercase()
+    }
+
     fn supports_zero_columns(&self) -> bool {
         false
     }
+
+    fn count_distinct_columns(&self) -> Option<CountDistinctColumns> {
+        Some(CountDistinctColumns::Row)
+    }
+
+    fn distinct_from(&self) -> Option<DistinctFrom> {
+        Some(DistinctFrom::Standard)
+    }
+
+    /// Whether a backslash in a string literal starts an escape sequence,
+    /// so literal backslashes (such as in regex patterns) must be doubled.
+    fn string_backslash_escapes(&self) -> bool {
+        false
+    }
+
+    /// Whether backslash escapes wildcards in LIKE patterns by default, so the
+    /// pattern needs no ESCAPE clause.
+    fn like_backslash_escapes(&self) -> bool {
+        false
+    }
+
+    /// Placeholder of the query parameter with the given number, counting
+    /// from 1. None if the dialect has no numbered placeholders, only `?`.
+    fn numbered_param(&self, number: usize) -> Option<String> {
+        Some(format!("${number}"))
+    }
 }
 
 impl dyn DialectHandler {
@@ -216,6 +364,16 @@ impl DialectHandler for GenericDialect {
 }
 
 impl DialectHandler for PostgresDialect {
+    // Dates are subtracted, which results in days
+    fn date_diff_units(&self) -> &'static [&'static str] {
+        &["day"]
+    }
+
+    // Used within an interval literal, such as `INTERVAL '1 day'`
+    fn translate_date_unit(&self, unit: &str) -> String {
+        unit.to_string()
+    }
+
     fn requires_quotes_intervals(&self) -> bool {
         true
     }
@@ -224,6 +382,11 @@ impl DialectHandler for PostgresDialect {
         true
     }
 
+    fn like_backslash_escapes(&self) -> bool {
+        // https://www.postgresql.org/docs/current/functions-matching.html#FUNCTIONS-LIKE
+        true
+    }
+
     // https://www.postgresql.org/docs/current/functions-formatting.html
     fn translate_chrono_item<'a>(&self, item: Item) -> Result<String> {
         Ok(match item {
@@ -293,6 +456,21 @@ impl DialectHandler for SQLiteDialect {
     fn stars_in_group(&self) -> bool {
         false
     }
+
+    fn count_distinct_columns(&self) -> Option<CountDistinctColumns> {
+        None
+    }
+
+    fn distinct_from(&self) -> Option<DistinctFrom> {
+        // https://www.sqlite.org/lang_expr.html#isisnot
+        Some(DistinctFrom::Is)
+    }
+
+    fn numbered_param(&self, number: usize) -> Option<String> {
+        // `$1` would be a named parameter, numbered by its position in the text
+        // https://www.sqlite.org/lang_expr.html#parameters
+        Some(format!("?{number}"))
+    }
 }
 
 impl DialectHandler for MsSqlDialect {
@@ -353,6 +531,14 @@ impl DialectHandler for MsSqlDialect {
             }
         })
     }
+
+    fn count_distinct_columns(&self) -> Option<CountDistinctColumns> {
+        None
+    }
+
+    fn numbered_param(&self, _number: usize) -> Option<String> {
+        None
+    }
 }
 
 impl DialectHandler for MySqlDialect {
@@ -395,6 +581,29 @@ impl DialectHandler for MySqlDialect {
             }
         })
     }
+
+    fn string_backslash_escapes(&self) -> bool {
+        // https://dev.mysql.com/doc/refman/8.0/en/string-literals.html
+        true
+    }
+
+    fn like_backslash_escapes(&self) -> bool {
+        // https://dev.mysql.com/doc/refman/8.0/en/string-comparison-functions.html#operator_like
+        true
+    }
+
+    fn count_distinct_columns(&self) -> Option<CountDistinctColumns> {
+        Some(CountDistinctColumns::List)
+    }
+
+    fn distinct_from(&self) -> Option<DistinctFrom> {
+        // https://dev.mysql.com/doc/refman/8.0/en/comparison-operators.html#operator_equal-to
+        Some(DistinctFrom::Spaceship)
+    }
+
+    fn numbered_param(&self, _number: usize) -> Option<String> {
+        None
+    }
 }
 
 impl DialectHandler for ClickHouseDialect {
@@ -446,6 +655,29 @@ impl DialectHandler for ClickHouseDialect {
             }
         })
     }
+
+    fn string_backslash_escapes(&self) -> bool {
+        // https://clickhouse.com/docs/en/sql-reference/syntax#string
+        true
+    }
+
+    fn like_backslash_escapes(&self) -> bool {
+        // https://clickhouse.com/docs/en/sql-reference/functions/string-search-functions#like
+        true
+    }
+
+    fn count_distinct_columns(&self) -> Option<CountDistinctColumns> {
+        Some(CountDistinctColumns::List)
+    }
+
+    fn distinct_from(&self) -> Option<DistinctFrom> {
+        // `isNotDistinctFrom` is only allowed in `JOIN ... ON`
+        None
+    }
+
+    fn numbered_param(&self, _number: usize) -> Option<String> {
+        None
+    }
 }
 
 impl DialectHandler for BigQueryDialect {
@@ -461,6 +693,34 @@ impl DialectHandler for BigQueryDialect {
         // https://cloud.google.com/bigquery/docs/reference/standard-sql/query-syntax#set_operators
         true
     }
+
+    fn string_backslash_escapes(&self) -> bool {
+        // https://cloud.google.com/bigquery/docs/reference/standard-sql/lexical#escape_sequences
+        true
+    }
+
+    fn like_backslash_escapes(&self) -> bool {
+        // https://cloud.google.com/bigquery/docs/reference/standard-sql/operators#like_operator
+        true
+    }
+
+    fn count_distinct_columns(&self) -> Option<CountDistinctColumns> {
+        None
+    }
+
+    fn supports_qualify(&self) -> bool {
+        // https://cloud.google.com/bigquery/docs/reference/standard-sql/query-syntax#qualify_clause
+        true
+    }
+
+    fn supports_group_by_all(&self) -> bool {
+        // https://cloud.google.com/bigquery/docs/reference/standard-sql/query-syntax#group_by_all
+        true
+    }
+
+    fn numbered_param(&self, _number: usize) -> Option<String> {
+        None
+    }
 }
 
 impl DialectHandler for SnowflakeDialect {
@@ -469,18 +729,46 @@ impl DialectHandler for SnowflakeDialect {
         Some(ColumnExclude::Exclude)
     }
 
+    fn supports_star_rename(&self) -> bool {
+        // https://docs.snowflake.com/en/sql-reference/sql/select#parameters
+        true
+    }
+
     fn set_ops_distinct(&self) -> bool {
         // https://docs.snowflake.com/en/sql-reference/operators-query.html
         false
     }
+
+    fn string_backslash_escapes(&self) -> bool {
+        // https://docs.snowflake.com/en/sql-reference/data-types-text#escape-sequences-in-single-quoted-string-constants
+        true
+    }
+
+    fn count_distinct_columns(&self) -> Option<CountDistinctColumns> {
+        Some(CountDistinctColumns::List)
+    }
+
+    fn numbered_param(&self, number: usize) -> Option<String> {
+        // https://docs.snowflake.com/en/developer-guide/sql-api/submitting-requests#using-bind-variables-in-a-statement
+        Some(format!(":{number}"))
+    }
 }
 
 impl DialectHandler for DuckDbDialect {
+    fn translate_date_unit(&self, unit: &str) -> String {
+        unit.to_string()
+    }
+
     fn column_exclude(&self) -> Option<ColumnExclude> {
         // https://duckdb.org/2022/05/04/friendlier-sql.html#select--exclude
         Some(ColumnExclude::Exclude)
     }
 
+    fn supports_star_rename(&self) -> bool {
+        // https://duckdb.org/docs/sql/expressions/star#rename-clause
+        true
+    }
+
     fn except_all(&self) -> bool {
         // https://duckdb.org/docs/sql/query_syntax/setops.html
         false
@@ -490,6 +778,11 @@ impl DialectHandler for DuckDbDialect {
         true
     }
 
+    fn supports_group_by_all(&self) -> bool {
+        // https://duckdb.org/docs/sql/query_syntax/groupby#group-by-all
+        true
+    }
+
     // https://duckdb.org/docs/sql/functions/dateformat
     fn translate_chrono_item<'a>(&self, item: Item) -> Result<String> {
         Ok(match item {
@@ -0,0 +1,631 @@
+//! Translation of SQL queries into PRQL, to help migrating existing queries.
+//!
+//! This covers a subset of SELECT: a FROM with joins, WHERE, GROUP BY,
+//! HAVING, ORDER BY, LIMIT & OFFSET and DISTINCT. Expressions that don't
+//! have a PRQL counterpart are kept as s-strings.
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use sqlparser::ast as sql_ast;
+use sqlparser::parser::Parser;
+
+use super::Dialect;
+use crate::pr::{self, BinOp, Literal, UnOp};
+use crate::{Error, Result, WithErrorInfo};
+
+/// Parses a SQL query and translates it into PR statements.
+pub(crate) fn sql_to_pr(sql: &str, dialect: Dialect) -> Result<Vec<pr::Stmt>> {
+    let statements = Parser::parse_sql(dialect.parser_dialect().as_ref(), sql)
+        .map_err(|e| Error::new_simple(e.to_string()))?;
+
+    let Ok(sql_ast::Statement::Query(query)) = statements.into_iter().exactly_one() else {
+        return Err(Error::new_simple("expected exactly one SELECT query"));
+    };
+    let pipeline = translate_query(*query)?;
+
+    Ok(vec![pr::Stmt::new(pr::StmtKind::VarDef(pr::VarDef {
+        kind: pr::VarDefKind::Main,
+        name: "main".to_string(),
+        value: Some(Box::new(pipeline)),
+        ty: None,
+    }))])
+}
+
+fn unsupported(what: &str) -> Error {
+    Error::new_simple(format!("{what} cannot be translated to PRQL"))
+        .push_hint("translate this part by hand, or use an s-string")
+}
+
+fn translate_query(query: sql_ast::Query) -> Result<pr::Expr> {
+    if query.with.is_some() {
+        return Err(unsupported("WITH"));
+    }
+    if query.fetch.is_some() {
+        return Err(unsupported("FETCH"));
+    }
+    let sql_ast::SetExpr::Select(select) = *query.body else {
+        return Err(unsupported("a set operation"));
+    };
+    let select = *select;
+
+    let mut pipeline = translate_from(select.from)?;
+
+    if let Some(selection) = select.selection {
+        pipeline.push(func_call("filter", vec![translate_expr(selection)?]));
+    }
+
+    // named outputs, which ORDER BY and HAVING may refer to
+    let aliases: Vec<_> = (select.projection.iter())
+        .filter_map(|item| match item {
+            sql_ast::SelectItem::ExprWithAlias { expr, alias } => {
+                Some((expr.clone(), alias.value.clone()))
+            }
+            _ => None,
+        })
+        .collect();
+
+    let group_by = match select.group_by {
+        sql_ast::GroupByExpr::Expressions(exprs, modifiers) if modifiers.is_empty() => exprs,
+        _ => return Err(unsupported("GROUP BY ALL or with modifiers")),
+    };
+    let is_aggregation = !group_by.is_empty()
+        || (select.projection.iter()).any(|item| match item {
+            sql_ast::SelectItem::UnnamedExpr(expr)
+            | sql_ast::SelectItem::ExprWithAlias { expr, .. } => contains_aggregate(expr),
+            _ => false,
+        });
+
+    let mut sort = query
+        .order_by
+        .map(|order_by| translate_order_by(order_by.exprs, &aliases))
+        .transpose()?;
+
+    if is_aggregation {
+        pipeline.push(translate_aggregation(select.projection, group_by)?);
+
+        if let Some(having) = select.having {
+            let having = replace_aliased(having, &aliases);
+            if contains_aggregate(&having) {
+                return Err(unsupported(
+                    "an aggregation in HAVING that is not in SELECT",
+                ));
+            }
+            pipeline.push(func_call("filter", vec![translate_expr(having)?]));
+        }
+    } else {
+        let (projection, names) = translate_projection(select.projection)?;
+
+        // sorting by columns that are not selected must happen before `select`
+        if !(sort.iter()).all(|sort| sorts_by_names(sort, names.as_deref())) {
+            pipeline.extend(sort.take());
+        }
+        pipeline.extend(projection);
+
+        match select.distinct {
+            None => {}
+            Some(sql_ast::Distinct::Distinct) => {
+                let Some(names) = names else {
+                    return Err(unsupported("SELECT DISTINCT *"));
+                };
+                let columns = names.into_iter().map(ident).collect();
+                let take = func_call("take", vec![literal(Literal::Integer(1))]);
+                pipeline.push(func_call("group", vec![tuple(columns), take]));
+            }
+            Some(sql_ast::Distinct::On(_)) => return Err(unsupported("DISTINCT ON")),
+        }
+    }
+
+    pipeline.extend(sort);
+
+    if let Some(take) = translate_limit(query.limit, query.offset)? {
+        pipeline.push(take);
+    }
+
+    Ok(pr::Expr::new(pr::ExprKind::Pipeline(pr::Pipeline {
+        exprs: pipeline,
+    })))
+}
+
+fn translate_from(from: Vec<sql_ast::TableWithJoins>) -> Result<Vec<pr::Expr>> {
+    let Ok(from) = from.into_iter().exactly_one() else {
+        return Err(unsupported("a SELECT without exactly one table in FROM"));
+    };
+
+    let mut pipeline = vec![func_call("from", vec![translate_relation(from.relation)?])];
+
+    for join in from.joins {
+        use sql_ast::JoinOperator::*;
+        let (side, constraint) = match join.join_operator {
+            Inner(constraint) => (None, constraint),
+            LeftOuter(constraint) => (Some("left"), constraint),
+            RightOuter(constraint) => (Some("right"), constraint),
+            FullOuter(constraint) => (Some("full"), constraint),
+            _ => return Err(unsupported("this kind of join")),
+        };
+        let sql_ast::JoinConstraint::On(condition) = constraint else {
+            return Err(unsupported("a join without ON"));
+        };
+
+        let mut call = pr::FuncCall {
+            name: Box::new(ident("join")),
+            args: vec![
+                translate_relation(join.relation)?,
+                translate_expr(condition)?,
+            ],
+            named_args: HashMap::new(),
+        };
+        if let Some(side) = side {
+            call.named_args.insert("side".to_string(), ident(side));
+        }
+        pipeline.push(pr::Expr::new(pr::ExprKind::FuncCall(call)));
+    }
+    Ok(pipeline)
+}
+
+fn translate_relation(relation: sql_ast::TableFactor) -> Result<pr::Expr> {
+    let (mut expr, alias) = match relation {
+        sql_ast::TableFactor::Table { name, alias, .. } => {
+            (ident_parts(name.0.into_iter().map(|i| i.value)), alias)
+        }
+        sql_ast::TableFactor::Derived {
+            lateral: false,
+            subquery,
+            alias,
+        } => (translate_query(*subquery)?, alias),
+        _ => return Err(unsupported("this kind of relation")),
+    };
+    if let Some(alias) = alias {
+        if !alias.columns.is_empty() {
+            return Err(unsupported("a table alias with columns"));
+        }
+        expr.alias = Some(alias.name.value);
+    }
+    Ok(expr)
+}
+
+/// Translates the projection of a query that does not aggregate. Returns the
+/// transform, and the names of the output columns, unless it includes `*`.
+fn translate_projection(
+    projection: Vec<sql_ast::SelectItem>,
+) -> Result<(Option<pr::Expr>, Option<Vec<String>>)> {
+    let mut has_wildcard = false;
+    let mut exprs = Vec::new();
+    let mut names = Vec::new();
+    for item in projection {
+        match item {
+            sql_ast::SelectItem::Wildcard(_) => has_wildcard = true,
+            sql_ast::SelectItem::UnnamedExpr(expr) => {
+                if let Some(name) = column_name(&expr) {
+                    names.push(name);
+                }
+                exprs.push(translate_expr(expr)?);
+            }
+            sql_ast::SelectItem::ExprWithAlias { expr, alias } => {
+                let mut expr = translate_expr(expr)?;
+                expr.alias = Some(alias.value.clone());
+                names.push(alias.value);
+                exprs.push(expr);
+            }
+            sql_ast::SelectItem::QualifiedWildcard(..) => {
+                return Err(unsupported("a qualified wildcard"))
+            }
+        }
+    }
+
+    Ok(match (has_wildcard, exprs.is_empty()) {
+        (true, true) => (None, None),
+        (true, false) => {
+            // derived columns are added after the columns of the relation
+            if exprs.iter().any(|e| e.alias.is_none()) {
+                return Err(unsupported("`*` together with unnamed columns"));
+            }
+            (Some(func_call("derive", vec![tuple(exprs)])), None)
+        }
+        (false, _) => (Some(func_call("select", vec![tuple(exprs)])), Some(names)),
+    })
+}
+
+fn translate_aggregation(
+    projection: Vec<sql_ast::SelectItem>,
+    group_by: Vec<sql_ast::Expr>,
+) -> Result<pr::Expr> {
+    let mut keys: Vec<_> = group_by.iter().map(|k| (k, None)).collect();
+    let mut aggregates = Vec::new();
+
+    for item in projection {
+        let (expr, alias) = match item {
+            sql_ast::SelectItem::UnnamedExpr(expr) => (expr, None),
+            sql_ast::SelectItem::ExprWithAlias { expr, alias } => (expr, Some(alias.value)),
+            _ => return Err(unsupported("`*` in a query with GROUP BY")),
+        };
+
+        if let Some(key) = keys.iter_mut().find(|(k, _)| **k == expr) {
+            key.1 = alias;
+        } else if contains_aggregate(&expr) {
+            let mut expr = translate_expr(expr)?;
+            expr.alias = alias;
+            aggregates.push(expr);
+        } else {
+            return Err(unsupported(
+                "a column that is neither grouped nor aggregated",
+            ));
+        }
+    }
+
+    let aggregate = func_call("aggregate", vec![tuple(aggregates)]);
+    if keys.is_empty() {
+        return Ok(aggregate);
+    }
+
+    let keys = keys
+        .into_iter()
+        .map(|(key, alias)| {
+            let mut key = translate_expr(key.clone())?;
+            key.alias = alias;
+            Ok(key)
+        })
+        .collect::<Result<_>>()?;
+    Ok(func_call("group", vec![tuple(keys), aggregate]))
+}
+
+fn translate_order_by(
+    order_by: Vec<sql_ast::OrderByExpr>,
+    aliases: &[(sql_ast::Expr, String)],
+) -> Result<pr::Expr> {
+    let columns = order_by
+        .into_iter()
+        .map(|order| {
+            if order.nulls_first.is_some() {
+                return Err(unsupported("NULLS FIRST or NULLS LAST"));
+            }
+            let expr = translate_expr(replace_aliased(order.expr, aliases))?;
+            Ok(if order.asc == Some(false) {
+                pr::Expr::new(pr::ExprKind::Unary(pr::UnaryExpr {
+                    op: UnOp::Neg,
+                    expr: Box::new(expr),
+                }))
+            } else {
+                expr
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(func_call("sort", vec![tuple(columns)]))
+}
+
+/// Whether a `sort` only refers to the given names, so it can follow `select`.
+fn sorts_by_names(sort: &pr::Expr, names: Option<&[String]>) -> bool {
+    let Some(names) = names else {
+        return true;
+    };
+    let pr::ExprKind::FuncCall(call) = &sort.kind else {
+        return false;
+    };
+    let pr::ExprKind::Tuple(columns) = &call.args[0].kind else {
+        return false;
+    };
+    columns.iter().all(|column| {
+        let column = match &column.kind {
+            pr::ExprKind::Unary(unary) => &unary.expr,
+            _ => column,
+        };
+        matches!(&column.kind, pr::ExprKind::Ident(name) if names.contains(name))
+    })
+}
+
+fn translate_limit(
+    limit: Option<sql_ast::Expr>,
+    offset: Option<sql_ast::Offset>,
+) -> Result<Option<pr::Expr>> {
+    let as_integer = |expr: sql_ast::Expr, clause: &str| match expr {
+        sql_ast::Expr::Value(sql_ast::Value::Number(n, _)) => n
+            .parse::<i64>()
+            .map_err(|_| unsupported(&format!("a non-integer {clause}"))),
+        _ => Err(unsupported(&format!("a non-literal {clause}"))),
+    };
+    let limit = limit.map(|l| as_integer(l, "LIMIT")).transpose()?;
+    let offset = offset.map(|o| as_integer(o.value, "OFFSET")).transpose()?;
+
+    let int = |i| Box::new(literal(Literal::Integer(i)));
+    let range = match (offset, limit) {
+        (None, None) => return Ok(None),
+        (None, Some(limit)) => literal(Literal::Integer(limit)),
+        (Some(offset), limit) => pr::Expr::new(pr::ExprKind::Range(pr::Range {
+            start: Some(int(offset + 1)),
+            end: limit.map(|limit| int(offset + limit)),
+        })),
+    };
+    Ok(Some(func_call("take", vec![range])))
+}
+
+fn translate_expr(expr: sql_ast::Expr) -> Result<pr::Expr> {
+    use sql_ast::Expr as E;
+
+    Ok(match expr {
+        E::Identifier(ident) => self::ident(ident.value),
+        E::CompoundIdentifier(parts) => ident_parts(parts.into_iter().map(|i| i.value)),
+        E::Value(value) => match value {
+            sql_ast::Value::Number(n, _) => literal(match n.parse::<i64>() {
+                Ok(i) => Literal::Integer(i),
+                Err(_) => Literal::Float(n.parse().map_err(|_| unsupported(&n))?),
+            }),
+            sql_ast::Value::SingleQuotedString(s) => literal(Literal::String(s)),
+            sql_ast::Value::Boolean(b) => literal(Literal::Boolean(b)),
+            sql_ast::Value::Null => literal(Literal::Null),
+            value => sstring(value.to_string()),
+        },
+        E::Nested(expr) => translate_expr(*expr)?,
+        E::BinaryOp { left, op, right } => {
+            use sql_ast::BinaryOperator::*;
+            let op = match op {
+                Plus => BinOp::Add,
+                Minus => BinOp::Sub,
+                Multiply => BinOp::Mul,
+                Divide => BinOp::DivFloat,
+                Modulo => BinOp::Mod,
+                Eq => BinOp::Eq,
+                NotEq => BinOp::Ne,
+                Gt => BinOp::Gt,
+                Lt => BinOp::Lt,
+                GtEq => BinOp::Gte,
+                LtEq => BinOp::Lte,
+                And => BinOp::And,
+                Or => BinOp::Or,
+                op => return Ok(sstring(E::BinaryOp { left, op, right }.to_string())),
+            };
+            binary(translate_expr(*left)?, op, translate_expr(*right)?)
+        }
+        E::UnaryOp { op, expr } => {
+            let op = match op {
+                sql_ast::UnaryOperator::Not => UnOp::Not,
+                sql_ast::UnaryOperator::Minus => UnOp::Neg,
+                op => return Ok(sstring(E::UnaryOp { op, expr }.to_string())),
+            };
+            pr::Expr::new(pr::ExprKind::Unary(pr::UnaryExpr {
+                op,
+                expr: Box::new(translate_expr(*expr)?),
+            }))
+        }
+        E::IsNull(expr) => binary(translate_expr(*expr)?, BinOp::Eq, literal(Literal::Null)),
+        E::IsNotNull(expr) => binary(translate_expr(*expr)?, BinOp::Ne, literal(Literal::Null)),
+        E::Case {
+            operand,
+            conditions,
+            results,
+            else_result,
+        } => {
+            let mut cases: Vec<_> = conditions
+                .into_iter()
+                .zip(results)
+                .map(|(condition, value)| {
+                    let mut condition = translate_expr(condition)?;
+                    if let Some(operand) = &operand {
+                        condition = binary(translate_expr(*operand.clone())?, BinOp::Eq, condition);
+                    }
+                    Ok(pr::SwitchCase {
+                        condition: Box::new(condition),
+                        value: Box::new(translate_expr(value)?),
+                    })
+                })
+                .collect::<Result<_>>()?;
+            if let Some(else_result) = else_result {
+                cases.push(pr::SwitchCase {
+                    condition: Box::new(literal(Literal::Boolean(true))),
+                    value: Box::new(translate_expr(*else_result)?),
+                });
+            }
+            pr::Expr::new(pr::ExprKind::Case(cases))
+        }
+        E::Function(function) => translate_function(function)?,
+        expr => sstring(expr.to_string()),
+    })
+}
+
+/// Translates the aggregate functions that are in the standard library of
+/// PRQL; other functions are kept as s-strings.
+fn translate_function(function: sql_ast::Function) -> Result<pr::Expr> {
+    let Some(name) = aggregate_name(&function) else {
+        return Ok(sstring(function.to_string()));
+    };
+    let sql_ast::FunctionArguments::List(list) = &function.args else {
+        return Ok(sstring(function.to_string()));
+    };
+    let Ok(arg) = list.args.iter().exactly_one() else {
+        return Ok(sstring(function.to_string()));
+    };
+    let distinct = matches!(
+        list.duplicate_treatment,
+        Some(sql_ast::DuplicateTreatment::Distinct)
+    );
+
+    use sql_ast::{FunctionArg, FunctionArgExpr};
+    let (name, arg) = match (name, distinct, arg) {
+        ("count", false, FunctionArg::Unnamed(FunctionArgExpr::Wildcard)) => {
+            ("count", ident("this"))
+        }
+        ("count", true, FunctionArg::Unnamed(FunctionArgExpr::Expr(expr))) => {
+            ("count_distinct", translate_expr(expr.clone())?)
+        }
+        (name, false, FunctionArg::Unnamed(FunctionArgExpr::Expr(expr))) => {
+            (name, translate_expr(expr.clone())?)
+        }
+        _ => return Ok(sstring(function.to_string())),
+    };
+    Ok(func_call(name, vec![arg]))
+}
+
+/// The name in PRQL of an aggregate function, unless it's used as a window
+/// function or has clauses that PRQL doesn't have.
+fn aggregate_name(function: &sql_ast::Function) -> Option<&'static str> {
+    if function.over.is_some() || function.filter.is_some() || function.name.0.len() != 1 {
+        return None;
+    }
+    Some(match function.name.0[0].value.to_lowercase().as_str() {
+        "sum" => "sum",
+        "min" => "min",
+        "max" => "max",
+        "avg" => "average",
+        "count" => "count",
+        _ => return None,
+    })
+}
+
+fn contains_aggregate(expr: &sql_ast::Expr) -> bool {
+    use sql_ast::Expr as E;
+    match expr {
+        E::Function(function) => aggregate_name(function).is_some(),
+        E::BinaryOp { left, right, .. } => contains_aggregate(left) || contains_aggregate(right),
+        E::UnaryOp { expr, .. }
+        | E::Nested(expr)
+        | E::IsNull(expr)
+        | E::IsNotNull(expr)
+        | E::Cast { expr, .. } => contains_aggregate(expr),
+        E::Case {
+            operand,
+            conditions,
+            results,
+            else_result,
+        } => (operand.iter().chain(else_result))
+            .map(|e| e.as_ref())
+            .chain(conditions)
+            .chain(results)
+            .any(contains_aggregate),
+        _ => false,
+    }
+}
+
+/// Replaces expressions of the projection that have an alias with the alias,
+/// as ORDER BY and HAVING may repeat them.
+fn replace_aliased(expr: sql_ast::Expr, aliases: &[(sql_ast::Expr, String)]) -> sql_ast::Expr {
+    use sql_ast::Expr as E;
+
+    if let Some((_, alias)) = aliases.iter().find(|(e, _)| *e == expr) {
+        return E::Identifier(sql_ast::Ident::new(alias));
+    }
+    match expr {
+        E::BinaryOp { left, op, right } => E::BinaryOp {
+            left: Box::new(replace_aliased(*left, aliases)),
+            op,
+            right: Box::new(replace_aliased(*right, aliases)),
+        },
+        E::UnaryOp { op, expr } => E::UnaryOp {
+            op,
+            expr: Box::new(replace_aliased(*expr, aliases)),
+        },
+        E::Nested(expr) => E::Nested(Box::new(replace_aliased(*expr, aliases))),
+        expr => expr,
+    }
+}
+
+fn column_name(expr: &sql_ast::Expr) -> Option<String> {
+    match expr {
+        sql_ast::Expr::Identifier(ident) => Some(ident.value.clone()),
+        sql_ast::Expr::CompoundIdentifier(parts) => parts.last().map(|i| i.value.clone()),
+        _ => None,
+    }
+}
+
+fn ident(name: impl Into<String>) -> pr::Expr {
+    pr::Expr::new(pr::ExprKind::Ident(name.into()))
+}
+
+fn ident_parts(mut parts: impl Iterator<Item = String>) -> pr::Expr {
+    let first = ident(parts.next().unwrap_or_default());
+    parts.fold(first, |base, name| {
+        pr::Expr::new(pr::ExprKind::Indirection {
+            base: Box::new(base),
+            field: pr::IndirectionKind::Name(name),
+        })
+    })
+}
+
+fn literal(literal: Literal) -> pr::Expr {
+    pr::Expr::new(pr::ExprKind::Literal(literal))
+}
+
+fn sstring(sql: String) -> pr::Expr {
+    pr::Expr::new(pr::ExprKind::SString(vec![pr::InterpolateItem::String(
+        sql,
+    )]))
+}
+
+fn tuple(fields: Vec<pr::Expr>) -> pr::Expr {
+    pr::Expr::new(pr::ExprKind::Tuple(fields))
+}
+
+fn binary(left: pr::Expr, op: BinOp, right: pr::Expr) -> pr::Expr {
+    pr::Expr::new(pr::ExprKind::Binary(pr::BinaryExpr {
+        left: Box::new(left),
+        op,
+        right: Box::new(right),
+    }))
+}
+
+fn func_call(name: &str, args: Vec<pr::Expr>) -> pr::Expr {
+    pr::Expr::new(pr::ExprKind::FuncCall(pr::FuncCall {
+        name: Box::new(ident(name)),
+        args,
+        named_args: HashMap::new(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+
+    use super::*;
+
+    /// Translates SQL to PRQL, checking that the PRQL compiles.
+    fn to_prql(sql: &str) -> String {
+        let prql = crate::sql_to_prql(sql, Dialect::Generic).unwrap();
+        if let Err(e) = crate::compile(&prql, &crate::Options::default()) {
+            panic!("{prql}\ndoes not compile: {e}");
+        }
+        prql
+    }
+
+    #[test]
+    fn test_aggregation() {
+        assert_snapshot!(to_prql(r#"
+            SELECT d.title, AVG(e.salary) AS avg_salary, COUNT(*) AS ct
+            FROM employees e
+            LEFT JOIN departments d ON e.dept_id = d.id
+            GROUP BY d.title
+            HAVING COUNT(*) > 10
+            ORDER BY avg_salary DESC
+        "#), @r"
+        from e = employees
+        join side:left d = departments (e.dept_id == d.id)
+        group {d.title} (aggregate {
+          avg_salary = average e.salary,
+          ct = count this,
+        })
+        filter ct > 10
+        sort {-avg_salary}
+        ");
+    }
+
+    #[test]
+    fn test_distinct_and_offset() {
+        assert_snapshot!(to_prql(r#"
+            SELECT DISTINCT a, b FROM t WHERE c IS NOT NULL ORDER BY a LIMIT 10 OFFSET 20
+        "#), @r"
+        from t
+        filter c != null
+        select {a, b}
+        group {a, b} (take 1)
+        sort {a}
+        take 21..30
+        ");
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let err = crate::sql_to_prql("SELECT a FROM t UNION SELECT a FROM u", Dialect::Generic)
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("a set operation cannot be translated to PRQL"));
+    }
+}
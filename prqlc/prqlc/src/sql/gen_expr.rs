@@ -11,6 +11,7 @@ use sqlparser::ast::{
     WindowFrameBound, WindowSpec,
 };
 
+use super::dialect::{CountDistinctColumns, DistinctFrom, IdentCase, IdentQuoting, DATE_UNITS};
 use super::gen_projection::try_into_exprs;
 use super::{keywords, Context};
 use crate::ir::generic::{ColumnSort, SortDirection, WindowFrame, WindowKind};
@@ -102,11 +103,29 @@ pub(super) fn translate_expr(expr: rq::Expr, ctx: &mut Context) -> Result<ExprOr
                         }
                     }
                 }
+                "std.is_distinct_from" | "std.is_not_distinct_from" => {
+                    return Ok(process_distinct_from(expr, ctx)?.into())
+                }
                 "std.concat" => return Ok(process_concat(&expr, ctx)?.into()),
+                "std.coalesce" => return Ok(process_coalesce(&expr, ctx)?.into()),
+                "std.greatest" | "std.least" => return process_greatest_least(expr, ctx),
+                "std.lag" | "std.lead" => return process_lag_lead(expr, ctx),
+                "std.count_distinct" if args.len() > 1 => {
+                    return process_count_distinct_columns(expr, ctx)
+                }
                 "std.array_in" => return Ok(process_array_in(&expr, args, ctx)?.into()),
                 "std.date.to_text" => {
                     return Ok(process_date_to_text(&expr, name, args, ctx)?.into())
                 }
+                "std.date.to_char" => return Ok(process_date_to_char(&expr, args, ctx)?.into()),
+                "std.text.starts_with"
+                | "std.text.contains"
+                | "std.text.ends_with"
+                | "std.text.istarts_with"
+                | "std.text.icontains"
+                | "std.text.iends_with" => return process_like(expr, ctx),
+                "std.array_get" | "std.array_slice" => return process_array_subscript(expr, ctx),
+                "std.date.diff" | "std.date.add" => return process_date_unit(expr, ctx),
                 _ => match try_into_between(expr.clone(), ctx)? {
                     Some(between_expr) => return Ok(between_expr.into()),
                     None => {
@@ -156,6 +175,50 @@ fn process_null(name: &str, args: &[rq::Expr], ctx: &mut Context) -> Result<sql_
     }
 }
 
+/// Translates null-safe comparisons, which are written differently in some
+/// dialects.
+fn process_distinct_from(expr: rq::Expr, ctx: &mut Context) -> Result<sql_ast::Expr> {
+    let span = expr.span;
+    let (name, args) = expr.kind.into_operator().unwrap();
+    let [left, right]: [rq::Expr; 2] = args.try_into().unwrap();
+
+    let Some(style) = ctx.dialect.distinct_from() else {
+        return Err(Error::new_simple(format!(
+            "operator {name} is not supported for dialect {}",
+            ctx.dialect_enum
+        ))
+        .with_span(span));
+    };
+    let distinct = name == "std.is_distinct_from";
+
+    // all of the forms bind like a comparison
+    let strength = BinaryOperator::Eq.binding_strength();
+    let left = translate_operand(left, true, strength, Associativity::Both, ctx)?.into_ast();
+    let right = translate_operand(right, false, strength, Associativity::Both, ctx)?.into_ast();
+    let (left, right) = (Box::new(left), Box::new(right));
+
+    Ok(match style {
+        DistinctFrom::Standard if distinct => sql_ast::Expr::IsDistinctFrom(left, right),
+        DistinctFrom::Standard => sql_ast::Expr::IsNotDistinctFrom(left, right),
+        DistinctFrom::Spaceship => {
+            let op = BinaryOperator::Spaceship;
+            let expr = sql_ast::Expr::BinaryOp { left, op, right };
+            if distinct {
+                sql_ast::Expr::UnaryOp {
+                    op: UnaryOperator::Not,
+                    expr: Box::new(expr),
+                }
+            } else {
+                expr
+            }
+        }
+        DistinctFrom::Is => {
+            let op = BinaryOperator::Custom(if distinct { "IS NOT" } else { "IS" }.to_string());
+            sql_ast::Expr::BinaryOp { left, op, right }
+        }
+    })
+}
+
 /// Translates into IN (v1, v2, ...) if possible
 fn process_array_in(
     expr: &rq::Expr,
@@ -237,9 +300,202 @@ fn process_date_to_text(
     }
 }
 
+/// Translates a date format of Postgres' `TO_CHAR` (such as `YYYY-MM-DD`) to PRQL
+/// date format, which is then translated like the format of `std.date.to_text`
+fn process_date_to_char(
+    expr: &rq::Expr,
+    args: &[rq::Expr],
+    ctx: &mut Context,
+) -> Result<sql_ast::Expr> {
+    if let [date_format_exp @ rq::Expr {
+        kind: rq::ExprKind::Literal(Literal::String(date_format)),
+        ..
+    }, col_expr] = args
+    {
+        let date_format = rq::Expr {
+            kind: rq::ExprKind::Literal(Literal::String(
+                translate_to_char_format(date_format)
+                    .map_err(|e| e.with_span(date_format_exp.span))?,
+            )),
+            span: date_format_exp.span,
+        };
+        process_date_to_text(
+            expr,
+            "std.date.to_text",
+            &[date_format, col_expr.clone()],
+            ctx,
+        )
+    } else {
+        Err(
+            Error::new_simple("`std.date.to_char` only supports a string literal as format")
+                .with_span(expr.span),
+        )
+    }
+}
+
+/// Patterns of `TO_CHAR` and their `chrono` equivalents.
+/// Patterns that share a prefix are ordered longest first.
+const TO_CHAR_PATTERNS: &[(&str, &str)] = &[
+    ("FMMonth", "%B"),
+    ("FMDay", "%A"),
+    ("FMHH24", "%-H"),
+    ("FMMM", "%-m"),
+    ("FMDD", "%-d"),
+    ("YYYY", "%Y"),
+    ("YY", "%y"),
+    ("Month", "%B"),
+    ("Mon", "%b"),
+    ("MM", "%m"),
+    ("MI", "%M"),
+    ("Day", "%A"),
+    ("Dy", "%a"),
+    ("DD", "%d"),
+    ("HH24", "%H"),
+    ("HH12", "%I"),
+    ("HH", "%I"),
+    ("SS", "%S"),
+    ("US", "%f"),
+    ("AM", "%p"),
+    ("PM", "%p"),
+];
+
+fn translate_to_char_format(to_char_format: &str) -> Result<String> {
+    let mut res = String::new();
+    let mut rest = to_char_format;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some((pattern, item)) = TO_CHAR_PATTERNS.iter().find(|(p, _)| rest.starts_with(p)) {
+            res += item;
+            rest = &rest[pattern.len()..];
+        } else if c == '"' {
+            // double quoted text is output as is
+            let Some(len) = rest[1..].find('"') else {
+                return Err(Error::new_simple("unterminated quoted text in date format"));
+            };
+            res += &rest[1..=len].replace('%', "%%");
+            rest = &rest[len + 2..];
+        } else if c.is_ascii_alphabetic() {
+            return Err(
+                Error::new_simple(format!("unsupported date format pattern at `{rest}`"))
+                    .push_hint("text can be output as is by wrapping it in double quotes"),
+            );
+        } else {
+            if c == '%' {
+                res.push('%');
+            }
+            res.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    Ok(res)
+}
+
+/// Escapes the wildcards of a literal argument to functions that translate to
+/// LIKE, so they match literally.
+fn process_like(mut expr: rq::Expr, ctx: &mut Context) -> Result<ExprOrSource> {
+    let rq::ExprKind::Operator { args, .. } = &mut expr.kind else {
+        unreachable!()
+    };
+
+    // Backslash is the usual escape character, but dialects that need an
+    // ESCAPE clause get `!`, since `'\'` trips up the SQL formatter.
+    let escape_char = if ctx.dialect.like_backslash_escapes() {
+        '\\'
+    } else {
+        '!'
+    };
+
+    let mut escaped = false;
+    if let Some(rq::Expr {
+        kind: rq::ExprKind::Literal(Literal::String(pattern)),
+        ..
+    }) = args.first_mut()
+    {
+        if pattern.contains(['%', '_', escape_char]) {
+            *pattern = pattern
+                .chars()
+                .flat_map(|c| match c {
+                    '%' | '_' => vec![escape_char, c],
+                    c if c == escape_char => vec![escape_char, c],
+                    c => vec![c],
+                })
+                .collect();
+            escaped = true;
+        }
+    }
+
+    let mut source = super::operators::translate_operator_expr(expr, ctx)?;
+    if escaped && !ctx.dialect.like_backslash_escapes() {
+        if let ExprOrSource::Source(source) = &mut source {
+            source.text += &format!(" ESCAPE '{escape_char}'");
+        }
+    }
+    Ok(source)
+}
+
+/// Parenthesizes the array of a subscript unless it is a column name, since
+/// Postgres only allows subscripting anything else within parentheses.
+fn process_array_subscript(mut expr: rq::Expr, ctx: &mut Context) -> Result<ExprOrSource> {
+    let rq::ExprKind::Operator { args, .. } = &mut expr.kind else {
+        unreachable!()
+    };
+
+    let array = args.pop().unwrap();
+    let span = array.span;
+    let text = match translate_expr(array, ctx)? {
+        ExprOrSource::Expr(
+            ident @ (sql_ast::Expr::Identifier(_) | sql_ast::Expr::CompoundIdentifier(_)),
+        ) => ident.to_string(),
+        array => format!("({})", array.into_source()),
+    };
+    args.push(rq::Expr {
+        kind: rq::ExprKind::SString(vec![InterpolateItem::String(text)]),
+        span,
+    });
+
+    super::operators::translate_operator_expr(expr, ctx)
+}
+
+/// Translates the unit of date arithmetic, which must be a string literal, to
+/// the notation of the dialect
+fn process_date_unit(mut expr: rq::Expr, ctx: &mut Context) -> Result<ExprOrSource> {
+    let rq::ExprKind::Operator { name, args } = &mut expr.kind else {
+        unreachable!()
+    };
+
+    let supported = match name.as_str() {
+        "std.date.diff" => ctx.dialect.date_diff_units(),
+        _ => DATE_UNITS,
+    };
+
+    let unit = &mut args[0];
+    let text = match &unit.kind {
+        rq::ExprKind::Literal(Literal::String(text)) if supported.contains(&text.as_str()) => {
+            ctx.dialect.translate_date_unit(text)
+        }
+        rq::ExprKind::Literal(Literal::String(text)) if DATE_UNITS.contains(&text.as_str()) => {
+            return Err(Error::new_simple(format!(
+                "`{name}` only supports {} as unit for this dialect",
+                supported.iter().map(|u| format!("\"{u}\"")).join(", ")
+            ))
+            .with_span(unit.span))
+        }
+        _ => {
+            return Err(Error::new_simple(format!(
+                "`{name}` only supports one of {} as unit",
+                DATE_UNITS.iter().map(|u| format!("\"{u}\"")).join(", ")
+            ))
+            .with_span(unit.span))
+        }
+    };
+    unit.kind = rq::ExprKind::SString(vec![InterpolateItem::String(text)]);
+
+    super::operators::translate_operator_expr(expr, ctx)
+}
+
 fn process_concat(expr: &rq::Expr, ctx: &mut Context) -> Result<sql_ast::Expr> {
     if ctx.dialect.has_concat_function() {
-        let concat_args = collect_concat_args(expr);
+        let concat_args = collect_nested_args(expr, "std.concat");
 
         let args_list = concat_args
             .iter()
@@ -265,7 +521,7 @@ fn process_concat(expr: &rq::Expr, ctx: &mut Context) -> Result<sql_ast::Expr> {
             parameters: sql_ast::FunctionArguments::None,
         }))
     } else {
-        let concat_args = collect_concat_args(expr);
+        let concat_args = collect_nested_args(expr, "std.concat");
 
         let mut iter = concat_args.into_iter();
         let first_expr = iter.next().unwrap();
@@ -284,6 +540,131 @@ fn process_concat(expr: &rq::Expr, ctx: &mut Context) -> Result<sql_ast::Expr> {
     }
 }
 
+/// Translates nested coalesce operators into a single `COALESCE(a, b, c, ...)`
+fn process_coalesce(expr: &rq::Expr, ctx: &mut Context) -> Result<sql_ast::Expr> {
+    let args_list = collect_nested_args(expr, "std.coalesce")
+        .into_iter()
+        .map(|a| {
+            translate_expr(a.clone(), ctx)
+                .map(|x| FunctionArg::Unnamed(FunctionArgExpr::Expr(x.into_ast())))
+        })
+        .try_collect()?;
+
+    let args = sql_ast::FunctionArguments::List(FunctionArgumentList {
+        args: args_list,
+        clauses: vec![],
+        duplicate_treatment: None,
+    });
+
+    Ok(sql_ast::Expr::Function(Function {
+        name: ObjectName(vec![sql_ast::Ident::new("COALESCE")]),
+        args,
+        over: None,
+        filter: None,
+        null_treatment: None,
+        within_group: vec![],
+        parameters: sql_ast::FunctionArguments::None,
+    }))
+}
+
+/// Translates `greatest` and `least`. When `null_safe` is set, each operand
+/// falls back to the other one, so a `NULL` operand is skipped regardless of
+/// how the dialect's implementation treats it.
+fn process_greatest_least(expr: rq::Expr, ctx: &mut Context) -> Result<ExprOrSource> {
+    let span = expr.span;
+    let (name, args) = expr.kind.into_operator().unwrap();
+    let [null_safe, left, right]: [rq::Expr; 3] = args.try_into().unwrap();
+
+    let null_safe = match null_safe.kind {
+        rq::ExprKind::Literal(Literal::Boolean(b)) => b,
+        _ => {
+            return Err(Error::new_simple("`null_safe` must be a boolean literal")
+                .with_span(null_safe.span))
+        }
+    };
+
+    let args = if null_safe {
+        let coalesce = |a: &rq::Expr, b: &rq::Expr| rq::Expr {
+            kind: rq::ExprKind::Operator {
+                name: "std.coalesce".to_string(),
+                args: vec![a.clone(), b.clone()],
+            },
+            span: a.span,
+        };
+        vec![coalesce(&left, &right), coalesce(&right, &left)]
+    } else {
+        vec![left, right]
+    };
+
+    let expr = rq::Expr {
+        kind: rq::ExprKind::Operator { name, args },
+        span,
+    };
+    super::operators::translate_operator_expr(expr, ctx)
+}
+
+/// Translates `lag` and `lead`, which only pass their `default` to SQL when it
+/// is set.
+fn process_lag_lead(expr: rq::Expr, ctx: &mut Context) -> Result<ExprOrSource> {
+    let span = expr.span;
+    let (name, mut args) = expr.kind.into_operator().unwrap();
+
+    let name = if matches!(args[0].kind, rq::ExprKind::Literal(Literal::Null)) {
+        args.remove(0);
+        name
+    } else {
+        format!("{name}_default")
+    };
+
+    let expr = rq::Expr {
+        kind: rq::ExprKind::Operator { name, args },
+        span,
+    };
+    super::operators::translate_operator_expr(expr, ctx)
+}
+
+/// Passes multiple columns to `COUNT(DISTINCT ...)` as a single s-string arg.
+fn process_count_distinct_columns(expr: rq::Expr, ctx: &mut Context) -> Result<ExprOrSource> {
+    let span = expr.span;
+    let (name, args) = expr.kind.into_operator().unwrap();
+
+    let Some(style) = ctx.dialect.count_distinct_columns() else {
+        return Err(Error::new_simple(format!(
+            "counting distinct values of multiple columns is not supported for dialect {}",
+            ctx.dialect_enum
+        ))
+        .with_span(span));
+    };
+
+    let mut items = Vec::new();
+    for (i, arg) in args.into_iter().enumerate() {
+        if i > 0 {
+            items.push(InterpolateItem::String(", ".to_string()));
+        }
+        items.push(InterpolateItem::Expr {
+            expr: Box::new(arg),
+            format: None,
+        });
+    }
+    if let CountDistinctColumns::Row = style {
+        items.insert(0, InterpolateItem::String("(".to_string()));
+        items.push(InterpolateItem::String(")".to_string()));
+    }
+
+    let columns = rq::Expr {
+        kind: rq::ExprKind::SString(items),
+        span,
+    };
+    let expr = rq::Expr {
+        kind: rq::ExprKind::Operator {
+            name,
+            args: vec![columns],
+        },
+        span,
+    };
+    super::operators::translate_operator_expr(expr, ctx)
+}
+
 fn translate_binary_operator(
     left: &rq::Expr,
     right: &rq::Expr,
@@ -301,11 +682,13 @@ fn translate_binary_operator(
     Ok(sql_ast::Expr::BinaryOp { left, op, right })
 }
 
-fn collect_concat_args(expr: &rq::Expr) -> Vec<&rq::Expr> {
+/// Collects args of an associative operator, flattening nested calls of the same operator.
+fn collect_nested_args<'a>(expr: &'a rq::Expr, op_name: &str) -> Vec<&'a rq::Expr> {
     match &expr.kind {
-        rq::ExprKind::Operator { name, args } if name == "std.concat" => {
-            args.iter().flat_map(collect_concat_args).collect()
-        }
+        rq::ExprKind::Operator { name, args } if name == op_name => args
+            .iter()
+            .flat_map(|a| collect_nested_args(a, op_name))
+            .collect(),
         _ => vec![expr],
     }
 }
@@ -381,6 +764,11 @@ pub(super) fn translate_literal(l: Literal, ctx: &Context) -> Result<sql_ast::Ex
     Ok(match l {
         Literal::Null => sql_ast::Expr::Value(Value::Null),
         Literal::String(s) | Literal::RawString(s) => {
+            let s = if ctx.dialect.string_backslash_escapes() {
+                s.replace('\\', r"\\")
+            } else {
+                s
+            };
             sql_ast::Expr::Value(Value::SingleQuotedString(s))
         }
         Literal::Boolean(b) => sql_ast::Expr::Value(Value::Boolean(b)),
@@ -668,7 +1056,8 @@ pub(super) fn translate_select_item(cid: rq::CId, ctx: &mut Context) -> Result<S
 
     let expected = ctx.anchor.column_names.get(&cid);
 
-    if inferred_name != expected {
+    // the case of the inferred name has already been changed
+    if inferred_name.cloned() != expected.map(|n| apply_ident_case(n.clone(), ctx)) {
         // use expected name
         let ident = expected.cloned().unwrap_or_else(|| {
             // or use something that will not clash with other names
@@ -813,12 +1202,37 @@ pub(super) fn translate_ident(
 }
 
 pub(super) fn translate_ident_part(ident: String, ctx: &Context) -> sql_ast::Ident {
-    let is_bare = valid_ident().is_match(&ident);
+    if ident == "*" {
+        return sql_ast::Ident::new(ident);
+    }
 
-    if is_bare && !keywords::is_keyword(&ident) {
-        sql_ast::Ident::new(ident)
-    } else {
+    let ident = apply_ident_case(ident, ctx);
+
+    let is_quoted = match ctx.ident_quoting {
+        IdentQuoting::Always => true,
+        IdentQuoting::Never => false,
+        IdentQuoting::WhenNeeded => {
+            let is_bare = if ctx.ident_case == IdentCase::Upper {
+                valid_ident().is_match(&ident.to_lowercase())
+            } else {
+                valid_ident().is_match(&ident)
+            };
+            !is_bare || keywords::is_keyword(&ident, ctx.dialect_enum)
+        }
+    };
+
+    if is_quoted {
         sql_ast::Ident::with_quote(ctx.dialect.ident_quote(), ident)
+    } else {
+        sql_ast::Ident::new(ident)
+    }
+}
+
+fn apply_ident_case(ident: String, ctx: &Context) -> String {
+    match ctx.ident_case {
+        IdentCase::Preserve => ident,
+        IdentCase::Lower => ident.to_lowercase(),
+        IdentCase::Upper => ident.to_uppercase(),
     }
 }
 
@@ -933,6 +1347,8 @@ impl SQLExpression for sql_ast::Expr {
 
             sql_ast::Expr::IsNull(_) | sql_ast::Expr::IsNotNull(_) => 5,
 
+            sql_ast::Expr::IsDistinctFrom(..) | sql_ast::Expr::IsNotDistinctFrom(..) => 6,
+
             // all other items types bind stronger (function calls, literals, ...)
             _ => 20,
         }
@@ -953,7 +1369,10 @@ impl SQLExpression for BinaryOperator {
             Modulo | Multiply | Divide => 11,
             Minus | Plus => 10,
 
-            Gt | Lt | GtEq | LtEq | Eq | NotEq => 6,
+            Gt | Lt | GtEq | LtEq | Eq | NotEq | Spaceship => 6,
+
+            // `IS` and `IS NOT` of null-safe comparisons
+            Custom(_) => 6,
 
             And => 3,
             Or => 2,
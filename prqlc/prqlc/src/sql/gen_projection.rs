@@ -2,8 +2,8 @@ use std::collections::{HashMap, HashSet};
 
 use itertools::Itertools;
 use sqlparser::ast::{
-    self as sql_ast, ExceptSelectItem, ExcludeSelectItem, ObjectName, SelectItem,
-    WildcardAdditionalOptions,
+    self as sql_ast, ExceptSelectItem, ExcludeSelectItem, IdentWithAlias, ObjectName,
+    RenameSelectItem, SelectItem, WildcardAdditionalOptions,
 };
 
 use super::dialect::ColumnExclude;
@@ -11,7 +11,7 @@ use super::gen_expr::*;
 use super::pq::context::{AnchorContext, ColumnDecl};
 use super::Context;
 use crate::ir::pl::Ident;
-use crate::ir::rq::{CId, RelationColumn};
+use crate::ir::rq::{CId, ExprKind, RelationColumn};
 use crate::Result;
 use crate::{Error, Span, WithErrorInfo};
 
@@ -125,8 +125,30 @@ pub(super) fn translate_select_items(
     mut excluded: Excluded,
     ctx: &mut Context,
 ) -> Result<Vec<SelectItem>> {
+    let mut renamed = find_renames(&cols, &excluded, ctx);
+    if ctx.dialect.column_exclude().is_none() {
+        // the old name would be included with the star, next to the new one
+        if let Some((old, new)) = renamed.values().flatten().next() {
+            let span = match &ctx.anchor.column_decls[new] {
+                ColumnDecl::Compute(compute) => compute.expr.span,
+                _ => None,
+            };
+            let old = as_col_names(&HashSet::from([*old]), &ctx.anchor).join("");
+            return Err(Error::new_simple(format!(
+                "cannot rename `{old}`, as the other columns of the relation are not known"
+            ))
+            .push_hint("this dialect cannot exclude columns from `*`, so select the columns first")
+            .with_span(span));
+        }
+    }
+    if !ctx.dialect.supports_star_rename() {
+        renamed.clear();
+    }
+    let renames: HashSet<CId> = renamed.values().flatten().map(|(_, new)| *new).collect();
+
     let mut res: Vec<_> = cols
         .into_iter()
+        .filter(|cid| !renames.contains(cid))
         .map(|cid| {
             let decl = ctx.anchor.column_decls.get(&cid).unwrap();
 
@@ -142,10 +164,21 @@ pub(super) fn translate_select_items(
             let ident = translate_ident(table_name, Some("*".to_string()), ctx);
 
             // excluded columns
-            let opts = (excluded.remove(&cid))
+            let renamed = renamed.remove(&cid).unwrap_or_default();
+            let mut excluded = excluded.remove(&cid).unwrap_or_default();
+            for (old, _) in &renamed {
+                excluded.remove(old);
+            }
+            let mut opts = Some(excluded)
+                .filter(|e| !e.is_empty())
                 .and_then(|excluded| translate_exclude(ctx, excluded))
                 .unwrap_or_default();
 
+            // renamed columns
+            if !renamed.is_empty() {
+                opts.opt_rename = Some(RenameSelectItem::Multiple(translate_renames(renamed, ctx)));
+            }
+
             Ok(if ident.len() > 1 {
                 let mut object_name = ident;
                 object_name.pop();
@@ -171,6 +204,49 @@ pub(super) fn translate_select_items(
     Ok(res)
 }
 
+/// Finds the columns that are excluded from a star, only to be selected again
+/// under a new name, as `rename` does for columns that are not known.
+///
+/// Returns pairs of the excluded column and the renamed column, by star.
+fn find_renames(cols: &[CId], excluded: &Excluded, ctx: &Context) -> HashMap<CId, Vec<(CId, CId)>> {
+    let mut renamed: HashMap<CId, Vec<(CId, CId)>> = HashMap::new();
+    let mut used = HashSet::new();
+    for cid in cols {
+        let Some(ColumnDecl::Compute(compute)) = ctx.anchor.column_decls.get(cid) else {
+            continue;
+        };
+        let ExprKind::ColumnRef(old) = &compute.expr.kind else {
+            continue;
+        };
+        if compute.window.is_some() || !ctx.anchor.column_names.contains_key(cid) {
+            continue;
+        }
+
+        let star = excluded.iter().find(|(_, e)| e.contains(old));
+        if let Some((star, _)) = star {
+            // a column can be renamed only once
+            if used.insert(*old) {
+                renamed.entry(*star).or_default().push((*old, *cid));
+            }
+        }
+    }
+    renamed
+}
+
+fn translate_renames(renamed: Vec<(CId, CId)>, ctx: &mut Context) -> Vec<IdentWithAlias> {
+    renamed
+        .into_iter()
+        .map(|(old, new)| {
+            let old = as_col_names(&HashSet::from([old]), &ctx.anchor).join("");
+            let new = ctx.anchor.column_names[&new].clone();
+            IdentWithAlias {
+                ident: translate_ident_part(old, ctx),
+                alias: translate_ident_part(new, ctx),
+            }
+        })
+        .collect()
+}
+
 fn translate_exclude(
     ctx: &mut Context,
     excluded: HashSet<CId>,
@@ -13,19 +13,24 @@ use super::gen_expr::*;
 use super::gen_projection::*;
 use super::operators::translate_operator;
 use super::pq::ast::{Cte, CteKind, RelationExpr, RelationExprKind, SqlRelation, SqlTransform};
-use super::{Context, Dialect};
+use super::pq::context::ColumnDecl;
+use super::Context;
 use crate::debug;
 use crate::ir::pl::{JoinSide, Literal};
-use crate::ir::rq::{CId, Expr, ExprKind, RelationLiteral, RelationalQuery};
+use crate::ir::rq::{CId, Expr, ExprKind, RelationColumn, RelationLiteral, RelationalQuery};
 use crate::utils::{BreakUp, Pluck};
-use crate::{Error, Result, WithErrorInfo};
+use crate::{Error, Options, Result, WithErrorInfo};
 use prqlc_parser::generic::InterpolateItem;
 
 type Transform = SqlTransform<RelationExpr, ()>;
 
-pub fn translate_query(query: RelationalQuery, dialect: Option<Dialect>) -> Result<sql_ast::Query> {
+pub fn translate_query(query: RelationalQuery, options: &Options) -> Result<sql_ast::Query> {
+    let crate::Target::Sql(dialect) = options.target;
+
     // compile from RQ to PQ
     let (pq_query, mut ctx) = super::pq::compile_query(query, dialect)?;
+    ctx.ident_quoting = options.ident_quoting;
+    ctx.ident_case = options.ident_case;
 
     debug::log_stage(debug::Stage::Sql(debug::StageSql::Main));
     let mut query = translate_relation(pq_query.main_relation, &mut ctx)?;
@@ -90,11 +95,18 @@ fn translate_select_pipeline(
         })
         .try_collect()?;
 
-    let joins = pipeline
+    let (joins, semi_joins): (Vec<_>, Vec<_>) = pipeline
         .pluck(|t| t.into_join())
+        .into_iter()
+        .partition(|(side, ..)| !matches!(side, JoinSide::Semi | JoinSide::Anti));
+    let joins = joins
         .into_iter()
         .map(|j| translate_join(j, ctx))
         .collect::<Result<Vec<_>>>()?;
+    let exists: Vec<_> = semi_joins
+        .into_iter()
+        .map(|j| translate_exists(j, ctx))
+        .try_collect()?;
     if !joins.is_empty() {
         if let Some(from) = from.last_mut() {
             from.joins = joins;
@@ -111,6 +123,7 @@ fn translate_select_pipeline(
     let projection = translate_wildcards(&ctx.anchor, projection);
     let projection = translate_select_items(projection.0, projection.1, ctx)?;
 
+    let qualify = pipeline.pluck(|t| t.into_qualify());
     let order_by = pipeline.pluck(|t| t.into_sort());
     let takes = pipeline.pluck(|t| t.into_take());
     let is_distinct = pipeline.iter().any(|t| matches!(t, SqlTransform::Distinct));
@@ -136,15 +149,24 @@ fn translate_select_pipeline(
         pipeline.break_up(|t| matches!(t, Transform::Aggregate { .. } | Transform::Union { .. }));
 
     // WHERE and HAVING
-    let where_ = filter_of_conditions(before_agg.pluck(|t| t.into_filter()), ctx)?;
+    let mut conditions = before_agg.pluck(|t| t.into_filter());
+    conditions.extend(exists);
+    let where_ = filter_of_conditions(conditions, ctx)?;
     let having = filter_of_conditions(after_agg.pluck(|t| t.into_filter()), ctx)?;
+    let qualify = filter_of_conditions(qualify, ctx)?;
 
     // GROUP BY
     let aggregate = after_agg.pluck(|t| t.into_aggregate()).into_iter().next();
     let group_by: Vec<CId> = aggregate.map(|(part, _)| part).unwrap_or_default();
-    ctx.query.allow_stars = ctx.dialect.stars_in_group();
-    let group_by = sql_ast::GroupByExpr::Expressions(try_into_exprs(group_by, ctx, None)?, vec![]);
-    ctx.query.allow_stars = true;
+    let group_by = if ctx.dialect.supports_group_by_all() && contains_wildcard(&group_by, ctx) {
+        // all selected columns that are not aggregated are in the partition
+        sql_ast::GroupByExpr::All(vec![])
+    } else {
+        ctx.query.allow_stars = ctx.dialect.stars_in_group();
+        let exprs = try_into_exprs(group_by, ctx, None)?;
+        ctx.query.allow_stars = true;
+        sql_ast::GroupByExpr::Expressions(exprs, vec![])
+    };
 
     ctx.query.pre_projection = false;
 
@@ -231,6 +253,7 @@ fn translate_select_pipeline(
             selection: where_,
             group_by,
             having,
+            qualify,
             ..default_select()
         })))
     })
@@ -356,11 +379,71 @@ fn translate_join(
             JoinSide::Left => JoinOperator::LeftOuter(constraint),
             JoinSide::Right => JoinOperator::RightOuter(constraint),
             JoinSide::Full => JoinOperator::FullOuter(constraint),
+            JoinSide::Semi | JoinSide::Anti => unreachable!(),
         },
         global: false,
     })
 }
 
+/// Translates a semi or anti join into an `EXISTS` condition, since SQL has no
+/// syntax for them. The condition is returned as an s-string, so it can be
+/// combined with the filters of the query.
+///
+/// A semi join created by `value | in relation` becomes `IN (SELECT ...)`
+/// instead.
+fn translate_exists(
+    (side, with, filter): (JoinSide, RelationExpr, Expr),
+    ctx: &mut Context,
+) -> Result<Expr> {
+    if let ExprKind::Operator { name, args } = &filter.kind {
+        if let (JoinSide::Semi, "std.array_in", [value, column]) =
+            (&side, name.as_str(), args.as_slice())
+        {
+            let subquery = default_query(SetExpr::Select(Box::new(Select {
+                projection: vec![SelectItem::UnnamedExpr(
+                    translate_expr(column.clone(), ctx)?.into_ast(),
+                )],
+                from: vec![TableWithJoins {
+                    relation: translate_relation_expr(with, ctx)?,
+                    joins: vec![],
+                }],
+                ..default_select()
+            })));
+
+            return Ok(Expr {
+                kind: ExprKind::SString(vec![
+                    InterpolateItem::Expr {
+                        expr: Box::new(value.clone()),
+                        format: None,
+                    },
+                    InterpolateItem::String(format!(" IN ({subquery})")),
+                ]),
+                span: filter.span,
+            });
+        }
+    }
+
+    let subquery = default_query(SetExpr::Select(Box::new(Select {
+        projection: vec![SelectItem::UnnamedExpr(sql_ast::Expr::Value(
+            sql_ast::Value::Number("1".to_string(), false),
+        ))],
+        from: vec![TableWithJoins {
+            relation: translate_relation_expr(with, ctx)?,
+            joins: vec![],
+        }],
+        selection: Some(translate_expr(filter, ctx)?.into_ast()),
+        ..default_select()
+    })));
+
+    let not = if side == JoinSide::Anti { "NOT " } else { "" };
+    Ok(Expr {
+        kind: ExprKind::SString(vec![InterpolateItem::String(format!(
+            "{not}EXISTS ({subquery})"
+        ))]),
+        span: None,
+    })
+}
+
 fn translate_cte(cte: Cte, ctx: &mut Context) -> Result<(sql_ast::Cte, bool)> {
     let decl = ctx.anchor.lookup_table_decl(&cte.tid).unwrap();
     let cte_name = decl.name.clone().unwrap();
@@ -540,6 +623,15 @@ pub(super) fn translate_query_operator(
     ))))
 }
 
+fn contains_wildcard(cids: &[CId], ctx: &Context) -> bool {
+    cids.iter().any(|cid| {
+        matches!(
+            ctx.anchor.column_decls.get(cid),
+            Some(ColumnDecl::RelationColumn(_, _, RelationColumn::Wildcard))
+        )
+    })
+}
+
 fn filter_of_conditions(exprs: Vec<Expr>, context: &mut Context) -> Result<Option<sql_ast::Expr>> {
     Ok(if let Some(cond) = all(exprs) {
         Some(translate_expr(cond, context)?.into_ast())
@@ -5,44 +5,60 @@ use sqlparser::keywords::{
     Keyword, ALL_KEYWORDS, ALL_KEYWORDS_INDEX, RESERVED_FOR_COLUMN_ALIAS, RESERVED_FOR_TABLE_ALIAS,
 };
 
+use super::Dialect;
+
 /// True for keywords which we want to quote when translating to SQL.
 ///
-/// Currently we're being fairly permissive (over-quoting is not a big concern).
+/// Each dialect with its own list of reserved words only quotes those. Other
+/// dialects quote the reserved words of all of SQLite, Postgres and DuckDB, as
+/// over-quoting is not a big concern.
 // We're not including the full list from `SQL_KEYWORDS`, as that has terms such
-// as `ID`, instead we bring a few dialects' keywords in.
-pub(super) fn is_keyword(ident: &str) -> bool {
+// as `ID`.
+pub(super) fn is_keyword(ident: &str, dialect: Dialect) -> bool {
     let ident = ident.to_ascii_uppercase();
 
-    sql_keywords().contains(ident.as_str())
+    sql_keywords(dialect).contains(ident.as_str())
 }
 
-fn sql_keywords() -> &'static HashSet<&'static str> {
-    static SQL_KEYWORDS: OnceLock<HashSet<&str>> = OnceLock::new();
-    SQL_KEYWORDS.get_or_init(|| {
-        let mut m = HashSet::new();
-        m.extend(SQLITE_KEYWORDS);
-        m.extend(POSTGRES_KEYWORDS);
-        m.extend(DUCKDB_KEYWORDS);
+fn sql_keywords(dialect: Dialect) -> &'static HashSet<&'static str> {
+    static SQLITE: OnceLock<HashSet<&str>> = OnceLock::new();
+    static POSTGRES: OnceLock<HashSet<&str>> = OnceLock::new();
+    static DUCKDB: OnceLock<HashSet<&str>> = OnceLock::new();
+    static MYSQL: OnceLock<HashSet<&str>> = OnceLock::new();
+    static OTHER: OnceLock<HashSet<&str>> = OnceLock::new();
 
-        let reverse_index: HashMap<&Keyword, usize> = ALL_KEYWORDS_INDEX
-            .iter()
-            .enumerate()
-            .map(|(idx, kw)| (kw, idx))
-            .collect();
+    match dialect {
+        Dialect::SQLite => SQLITE.get_or_init(|| keyword_set(&[SQLITE_KEYWORDS])),
+        Dialect::Postgres => POSTGRES.get_or_init(|| keyword_set(&[POSTGRES_KEYWORDS])),
+        Dialect::DuckDb => DUCKDB.get_or_init(|| keyword_set(&[DUCKDB_KEYWORDS])),
+        Dialect::MySql => MYSQL.get_or_init(|| keyword_set(&[MYSQL_KEYWORDS])),
+        _ => OTHER
+            .get_or_init(|| keyword_set(&[SQLITE_KEYWORDS, POSTGRES_KEYWORDS, DUCKDB_KEYWORDS])),
+    }
+}
+
+/// The given keywords, plus those that sqlparser doesn't accept as an alias.
+fn keyword_set(lists: &[&[&'static str]]) -> HashSet<&'static str> {
+    let mut m: HashSet<&str> = lists.iter().flat_map(|l| l.iter().copied()).collect();
 
-        m.extend(
-            RESERVED_FOR_COLUMN_ALIAS
-                .iter()
-                .map(|x| ALL_KEYWORDS[reverse_index[x]]),
-        );
+    let reverse_index: HashMap<&Keyword, usize> = ALL_KEYWORDS_INDEX
+        .iter()
+        .enumerate()
+        .map(|(idx, kw)| (kw, idx))
+        .collect();
 
-        m.extend(
-            RESERVED_FOR_TABLE_ALIAS
-                .iter()
-                .map(|x| ALL_KEYWORDS[reverse_index[x]]),
-        );
-        m
-    })
+    m.extend(
+        RESERVED_FOR_COLUMN_ALIAS
+            .iter()
+            .map(|x| ALL_KEYWORDS[reverse_index[x]]),
+    );
+
+    m.extend(
+        RESERVED_FOR_TABLE_ALIAS
+            .iter()
+            .map(|x| ALL_KEYWORDS[reverse_index[x]]),
+    );
+    m
 }
 
 const SQLITE_KEYWORDS: &[&str] = &[
@@ -390,8 +406,282 @@ const DUCKDB_KEYWORDS: &[&str] = &[
     "WITH",
 ];
 
+// In MySQL 8.0:
+//
+//   SELECT CONCAT('"', WORD, '",') FROM information_schema.KEYWORDS WHERE RESERVED = 1;
+//
+const MYSQL_KEYWORDS: &[&str] = &[
+    "ACCESSIBLE",
+    "ADD",
+    "ALL",
+    "ALTER",
+    "ANALYZE",
+    "AND",
+    "AS",
+    "ASC",
+    "ASENSITIVE",
+    "BEFORE",
+    "BETWEEN",
+    "BIGINT",
+    "BINARY",
+    "BLOB",
+    "BOTH",
+    "BY",
+    "CALL",
+    "CASCADE",
+    "CASE",
+    "CHANGE",
+    "CHAR",
+    "CHARACTER",
+    "CHECK",
+    "COLLATE",
+    "COLUMN",
+    "CONDITION",
+    "CONSTRAINT",
+    "CONTINUE",
+    "CONVERT",
+    "CREATE",
+    "CROSS",
+    "CUBE",
+    "CUME_DIST",
+    "CURRENT_DATE",
+    "CURRENT_TIME",
+    "CURRENT_TIMESTAMP",
+    "CURRENT_USER",
+    "CURSOR",
+    "DATABASE",
+    "DATABASES",
+    "DAY_HOUR",
+    "DAY_MICROSECOND",
+    "DAY_MINUTE",
+    "DAY_SECOND",
+    "DEC",
+    "DECIMAL",
+    "DECLARE",
+    "DEFAULT",
+    "DELAYED",
+    "DELETE",
+    "DENSE_RANK",
+    "DESC",
+    "DESCRIBE",
+    "DETERMINISTIC",
+    "DISTINCT",
+    "DISTINCTROW",
+    "DIV",
+    "DOUBLE",
+    "DROP",
+    "DUAL",
+    "EACH",
+    "ELSE",
+    "ELSEIF",
+    "EMPTY",
+    "ENCLOSED",
+    "ESCAPED",
+    "EXCEPT",
+    "EXISTS",
+    "EXIT",
+    "EXPLAIN",
+    "FALSE",
+    "FETCH",
+    "FIRST_VALUE",
+    "FLOAT",
+    "FLOAT4",
+    "FLOAT8",
+    "FOR",
+    "FORCE",
+    "FOREIGN",
+    "FROM",
+    "FULLTEXT",
+    "FUNCTION",
+    "GENERATED",
+    "GET",
+    "GRANT",
+    "GROUP",
+    "GROUPING",
+    "GROUPS",
+    "HAVING",
+    "HIGH_PRIORITY",
+    "HOUR_MICROSECOND",
+    "HOUR_MINUTE",
+    "HOUR_SECOND",
+    "IF",
+    "IGNORE",
+    "IN",
+    "INDEX",
+    "INFILE",
+    "INNER",
+    "INOUT",
+    "INSENSITIVE",
+    "INSERT",
+    "INT",
+    "INT1",
+    "INT2",
+    "INT3",
+    "INT4",
+    "INT8",
+    "INTEGER",
+    "INTERSECT",
+    "INTERVAL",
+    "INTO",
+    "IO_AFTER_GTIDS",
+    "IO_BEFORE_GTIDS",
+    "IS",
+    "ITERATE",
+    "JOIN",
+    "JSON_TABLE",
+    "KEY",
+    "KEYS",
+    "KILL",
+    "LAG",
+    "LAST_VALUE",
+    "LATERAL",
+    "LEAD",
+    "LEADING",
+    "LEAVE",
+    "LEFT",
+    "LIKE",
+    "LIMIT",
+    "LINEAR",
+    "LINES",
+    "LOAD",
+    "LOCALTIME",
+    "LOCALTIMESTAMP",
+    "LOCK",
+    "LONG",
+    "LONGBLOB",
+    "LONGTEXT",
+    "LOOP",
+    "LOW_PRIORITY",
+    "MASTER_BIND",
+    "MASTER_SSL_VERIFY_SERVER_CERT",
+    "MATCH",
+    "MAXVALUE",
+    "MEDIUMBLOB",
+    "MEDIUMINT",
+    "MEDIUMTEXT",
+    "MIDDLEINT",
+    "MINUTE_MICROSECOND",
+    "MINUTE_SECOND",
+    "MOD",
+    "MODIFIES",
+    "NATURAL",
+    "NOT",
+    "NO_WRITE_TO_BINLOG",
+    "NTH_VALUE",
+    "NTILE",
+    "NULL",
+    "NUMERIC",
+    "OF",
+    "ON",
+    "OPTIMIZE",
+    "OPTIMIZER_COSTS",
+    "OPTION",
+    "OPTIONALLY",
+    "OR",
+    "ORDER",
+    "OUT",
+    "OUTER",
+    "OUTFILE",
+    "OVER",
+    "PARTITION",
+    "PERCENT_RANK",
+    "PRECISION",
+    "PRIMARY",
+    "PROCEDURE",
+    "PURGE",
+    "RANGE",
+    "RANK",
+    "READ",
+    "READS",
+    "READ_WRITE",
+    "REAL",
+    "RECURSIVE",
+    "REFERENCES",
+    "REGEXP",
+    "RELEASE",
+    "RENAME",
+    "REPEAT",
+    "REPLACE",
+    "REQUIRE",
+    "RESIGNAL",
+    "RESTRICT",
+    "RETURN",
+    "REVOKE",
+    "RIGHT",
+    "RLIKE",
+    "ROW",
+    "ROWS",
+    "ROW_NUMBER",
+    "SCHEMA",
+    "SCHEMAS",
+    "SECOND_MICROSECOND",
+    "SELECT",
+    "SENSITIVE",
+    "SEPARATOR",
+    "SET",
+    "SHOW",
+    "SIGNAL",
+    "SMALLINT",
+    "SPATIAL",
+    "SPECIFIC",
+    "SQL",
+    "SQLEXCEPTION",
+    "SQLSTATE",
+    "SQLWARNING",
+    "SQL_BIG_RESULT",
+    "SQL_CALC_FOUND_ROWS",
+    "SQL_SMALL_RESULT",
+    "SSL",
+    "STARTING",
+    "STORED",
+    "STRAIGHT_JOIN",
+    "SYSTEM",
+    "TABLE",
+    "TERMINATED",
+    "THEN",
+    "TINYBLOB",
+    "TINYINT",
+    "TINYTEXT",
+    "TO",
+    "TRAILING",
+    "TRIGGER",
+    "TRUE",
+    "UNDO",
+    "UNION",
+    "UNIQUE",
+    "UNLOCK",
+    "UNSIGNED",
+    "UPDATE",
+    "USAGE",
+    "USE",
+    "USING",
+    "UTC_DATE",
+    "UTC_TIME",
+    "UTC_TIMESTAMP",
+    "VALUES",
+    "VARBINARY",
+    "VARCHAR",
+    "VARCHARACTER",
+    "VARYING",
+    "VIRTUAL",
+    "WHEN",
+    "WHERE",
+    "WHILE",
+    "WINDOW",
+    "WITH",
+    "WRITE",
+    "XOR",
+    "YEAR_MONTH",
+    "ZEROFILL",
+];
+
 #[test]
 fn test_sql_keywords() {
-    assert!(is_keyword("from"));
-    assert!(is_keyword("user"));
+    assert!(is_keyword("from", Dialect::Generic));
+    assert!(is_keyword("user", Dialect::Generic));
+
+    assert!(is_keyword("usage", Dialect::MySql));
+    assert!(!is_keyword("usage", Dialect::Postgres));
+    assert!(is_keyword("key", Dialect::SQLite));
+    assert!(!is_keyword("key", Dialect::Postgres));
 }
@@ -1,42 +1,124 @@
 //! Backend for translating RQ into SQL
 
 mod dialect;
+mod from_sql;
 mod gen_expr;
 mod gen_projection;
 mod gen_query;
 mod keywords;
 mod operators;
+mod params;
 mod pq;
+mod simplify;
+mod source;
+mod writer;
 
-pub use dialect::{Dialect, SupportLevel};
+pub use dialect::{Dialect, IdentCase, IdentQuoting, SupportLevel};
 pub use pq::ast as pq_ast;
+pub use source::{DefaultSourceResolver, ResolvedSource, SourceResolver};
+#[cfg(feature = "sql-writer")]
+pub use sqlparser::ast as sql_ast;
+#[cfg(feature = "sql-writer")]
+pub use writer::{DefaultSqlWriter, SqlWriter};
+
+pub(crate) use from_sql::sql_to_pr;
+
+use itertools::Itertools;
+use regex::Regex;
 
 use self::dialect::DialectHandler;
 use self::pq::ast::Cte;
 use self::pq::context::AnchorContext;
 use crate::debug;
 use crate::ir::rq;
-use crate::Result;
 use crate::{compiler_version, Options};
+use crate::{Error, Result};
 
 /// Translate a PRQL AST into a SQL string.
 pub fn compile(query: rq::RelationalQuery, options: &Options) -> Result<String> {
+    compile_many(vec![query], options)
+}
+
+/// Translate multiple PRQL ASTs into SQL statements separated by `;`.
+pub fn compile_many(queries: Vec<rq::RelationalQuery>, options: &Options) -> Result<String> {
+    compile_with_source_resolver(queries, options, &DefaultSourceResolver)
+}
+
+/// Translate PRQL ASTs into SQL, replacing query parameters with the numbered
+/// placeholders of the dialect, such as `$1`.
+///
+/// Returns the SQL and the names of the parameters, ordered by their numbers.
+/// Parameters that have a value in [Options::params] are not included.
+pub fn compile_with_params(
+    queries: Vec<rq::RelationalQuery>,
+    options: &Options,
+) -> Result<(String, Vec<String>)> {
     let crate::Target::Sql(dialect) = options.target;
-    let sql_ast = gen_query::translate_query(query, dialect)?;
+    let dialect = dialect.unwrap_or_default().handler();
 
-    let sql = sql_ast.to_string();
+    let mut names = Vec::new();
+    let queries: Vec<_> = queries
+        .into_iter()
+        .map(|query| {
+            let query = params::bind_params(query, &options.params)?;
+            params::number_params(query, dialect.as_ref(), &mut names)
+        })
+        .try_collect()?;
 
-    // formatting
-    let sql = if options.format {
-        let formatted = sqlformat::format(
-            &sql,
-            &sqlformat::QueryParams::default(),
-            sqlformat::FormatOptions::default(),
-        );
+    let sql = compile_many(queries, options)?;
+    Ok((sql, names))
+}
+
+/// Translate PRQL ASTs into SQL, consulting `resolver` for each table that
+/// the queries reference but do not define.
+pub fn compile_with_source_resolver(
+    queries: Vec<rq::RelationalQuery>,
+    options: &Options,
+    resolver: &dyn SourceResolver,
+) -> Result<String> {
+    compile_with(queries, options, resolver, &writer::DefaultSqlWriter)
+}
+
+/// Translate PRQL ASTs into SQL, as [compile_with_source_resolver], rendering
+/// the generated SQL AST with `writer`.
+///
+/// Requires the `sql-writer` feature.
+#[cfg(feature = "sql-writer")]
+pub fn compile_with_writer(
+    queries: Vec<rq::RelationalQuery>,
+    options: &Options,
+    resolver: &dyn SourceResolver,
+    writer: &dyn SqlWriter,
+) -> Result<String> {
+    compile_with(queries, options, resolver, writer)
+}
 
-        formatted + "\n"
+fn compile_with(
+    queries: Vec<rq::RelationalQuery>,
+    options: &Options,
+    resolver: &dyn SourceResolver,
+    writer: &dyn writer::SqlWriter,
+) -> Result<String> {
+    let crate::Target::Sql(dialect) = options.target;
+
+    let statements: Vec<_> = queries
+        .into_iter()
+        .map(|query| {
+            let query = params::bind_params(query, &options.params)?;
+            let query = source::resolve_sources(query, resolver)?;
+            let query = if options.simplify {
+                simplify::simplify(query)?
+            } else {
+                query
+            };
+            translate_statement(query, options, writer)
+        })
+        .try_collect()?;
+
+    let sql = if options.format {
+        statements.join(";\n\n") + "\n"
     } else {
-        sql
+        statements.join("; ")
     };
 
     debug::log_entry(|| debug::DebugEntryKind::ReprSql(sql.clone()));
@@ -61,11 +143,72 @@ pub fn compile(query: rq::RelationalQuery, options: &Options) -> Result<String>
     Ok(sql)
 }
 
+/// Translate the RQ of a query that selects a single expression into the SQL
+/// of just that expression.
+pub(crate) fn compile_fragment(query: rq::RelationalQuery, options: &Options) -> Result<String> {
+    let mut query = params::bind_params(query, &options.params)?;
+    if options.simplify {
+        query = simplify::simplify(query)?;
+    }
+    let query = gen_query::translate_query(query, options)?;
+
+    let item = match *query.body {
+        sqlparser::ast::SetExpr::Select(select) if query.with.is_none() => {
+            select.projection.into_iter().exactly_one().ok()
+        }
+        _ => None,
+    };
+    match item {
+        Some(
+            sqlparser::ast::SelectItem::UnnamedExpr(expr)
+            | sqlparser::ast::SelectItem::ExprWithAlias { expr, .. },
+        ) => Ok(expr.to_string()),
+        _ => Err(Error::new_simple(
+            "the expression cannot be compiled into a SQL fragment",
+        )),
+    }
+}
+
+/// Translates a single query, without a trailing new line.
+fn translate_statement(
+    query: rq::RelationalQuery,
+    options: &Options,
+    writer: &dyn writer::SqlWriter,
+) -> Result<String> {
+    let sql_ast = gen_query::translate_query(query, options)?;
+
+    let sql = writer.write_query(&sql_ast);
+
+    // formatting
+    Ok(if options.format {
+        let sql = sqlformat::format(
+            &sql,
+            &sqlformat::QueryParams::default(),
+            sqlformat::FormatOptions::default(),
+        );
+        rejoin_operators(&sql)
+    } else {
+        sql
+    })
+}
+
+/// Joins operators that sqlformat splits because it doesn't know them:
+/// `IS [NOT] DISTINCT FROM`, which it reads as a FROM clause, and `<=>`.
+fn rejoin_operators(sql: &str) -> String {
+    let distinct_from = Regex::new(r"\bIS (NOT )?DISTINCT\n\s*FROM\n\s*").unwrap();
+    let sql = distinct_from.replace_all(sql, "IS ${1}DISTINCT FROM ");
+
+    sql.replace(" <= > ", " <=> ")
+}
+
 #[derive(Debug)]
 struct Context {
     pub dialect: Box<dyn DialectHandler>,
     pub dialect_enum: Dialect,
 
+    pub ident_quoting: IdentQuoting,
+    pub ident_case: IdentCase,
+
     pub anchor: AnchorContext,
 
     // stuff regarding current query
@@ -115,6 +258,8 @@ impl Context {
         Context {
             dialect: dialect.handler(),
             dialect_enum: dialect,
+            ident_quoting: IdentQuoting::default(),
+            ident_case: IdentCase::default(),
             anchor,
             query: QueryOpts::default(),
             query_stack: Vec::new(),
@@ -0,0 +1,81 @@
+//! Binding of query parameters, which are written as `$name` in PRQL.
+
+use std::collections::HashMap;
+
+use prqlc_parser::generic::InterpolateItem;
+
+use super::dialect::DialectHandler;
+use crate::ir::rq::{self, RqFold};
+use crate::lr::Literal;
+use crate::{Error, Result, WithErrorInfo};
+
+/// Replaces parameters that have a value with a literal of that value.
+pub(super) fn bind_params(
+    query: rq::RelationalQuery,
+    values: &HashMap<String, Literal>,
+) -> Result<rq::RelationalQuery> {
+    if values.is_empty() {
+        return Ok(query);
+    }
+    ParamBinder { values }.fold_query(query)
+}
+
+struct ParamBinder<'a> {
+    values: &'a HashMap<String, Literal>,
+}
+
+impl RqFold for ParamBinder<'_> {
+    fn fold_expr_kind(&mut self, kind: rq::ExprKind) -> Result<rq::ExprKind> {
+        Ok(match kind {
+            rq::ExprKind::Param(name) => match self.values.get(&name) {
+                Some(value) => rq::ExprKind::Literal(value.clone()),
+                None => rq::ExprKind::Param(name),
+            },
+            kind => rq::fold_expr_kind(self, kind)?,
+        })
+    }
+}
+
+/// Replaces parameters with the numbered placeholders of the dialect. Each
+/// name is numbered once, in order of first appearance, and pushed to `names`.
+pub(super) fn number_params(
+    query: rq::RelationalQuery,
+    dialect: &dyn DialectHandler,
+    names: &mut Vec<String>,
+) -> Result<rq::RelationalQuery> {
+    ParamNumberer { dialect, names }.fold_query(query)
+}
+
+struct ParamNumberer<'a> {
+    dialect: &'a dyn DialectHandler,
+    names: &'a mut Vec<String>,
+}
+
+impl RqFold for ParamNumberer<'_> {
+    fn fold_expr(&mut self, expr: rq::Expr) -> Result<rq::Expr> {
+        let rq::ExprKind::Param(name) = expr.kind else {
+            let kind = self.fold_expr_kind(expr.kind)?;
+            return Ok(rq::Expr { kind, ..expr });
+        };
+
+        let position = match self.names.iter().position(|n| n == &name) {
+            Some(position) => position,
+            None => {
+                self.names.push(name);
+                self.names.len() - 1
+            }
+        };
+        let Some(placeholder) = self.dialect.numbered_param(position + 1) else {
+            return Err(
+                Error::new_simple("this dialect does not support numbered parameters")
+                    .push_hint("provide the values of parameters with `Options::params` instead")
+                    .with_span(expr.span),
+            );
+        };
+
+        Ok(rq::Expr {
+            kind: rq::ExprKind::SString(vec![InterpolateItem::String(placeholder)]),
+            span: expr.span,
+        })
+    }
+}
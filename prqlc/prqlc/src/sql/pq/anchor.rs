@@ -6,8 +6,8 @@ use super::ast::{PqMapper, SqlTransform};
 use super::context::{AnchorContext, ColumnDecl, RIId, RelationStatus, SqlTableDecl};
 use crate::ir::generic::ColumnSort;
 use crate::ir::rq::{
-    self, fold_column_sorts, fold_transform, CId, Compute, Expr, RelationColumn, RqFold, TableRef,
-    Transform,
+    self, fold_column_sorts, fold_transform, visit_expr_kind, CId, Compute, Expr, RelationColumn,
+    RqFold, RqVisitor, TableRef, Transform,
 };
 use crate::sql::pq::context::RelationAdapter;
 use crate::Result;
@@ -18,7 +18,23 @@ pub(super) fn extract_atomic(
     pipeline: Vec<SqlTransform>,
     ctx: &mut AnchorContext,
 ) -> Vec<SqlTransform> {
-    let output = ctx.determine_select_columns(&pipeline);
+    let mut output = ctx.determine_select_columns(&pipeline);
+
+    // an aggregation changes the number of rows, so it has to be kept even
+    // when none of its columns are used
+    if output.is_empty() {
+        let aggregate = pipeline.iter().find_map(|t| match t {
+            SqlTransform::Super(Transform::Aggregate { partition, compute }) => {
+                Some(if compute.is_empty() {
+                    partition
+                } else {
+                    compute
+                })
+            }
+            _ => None,
+        });
+        output.extend(aggregate.into_iter().flatten());
+    }
 
     let (preceding, atomic) = split_off_back(pipeline, output.clone(), ctx);
 
@@ -302,6 +318,7 @@ fn is_split_required(transform: &SqlTransform, following: &mut HashSet<String>)
     // - aggregate (max 1x)
     // - filters (for HAVING)
     // - compute (no limit)
+    // - filters of window functions (for QUALIFY)
     // - sort (no limit)
     // - take (no limit)
     // - distinct
@@ -336,13 +353,25 @@ fn is_split_required(transform: &SqlTransform, following: &mut HashSet<String>)
             contains_any(following, ["From", "Join", "Aggregate", "Compute"])
         }
         Super(Filter(_)) => contains_any(following, ["From", "Join"]),
+        SqlTransform::Qualify(_) => contains_any(
+            following,
+            ["From", "Join", "Compute", "Filter", "Aggregate", "Qualify"],
+        ),
         Super(Compute(_)) => contains_any(following, ["From", "Join", /* "Aggregate" */ "Filter"]),
 
         // Sort will be pushed down the CTEs, so there is no point in splitting for it.
         // Super(Sort(_)) => contains_any(following, ["From", "Join", "Compute", "Aggregate"]),
         Super(Take(_)) => contains_any(
             following,
-            ["From", "Join", "Compute", "Filter", "Aggregate", "Sort"],
+            [
+                "From",
+                "Join",
+                "Compute",
+                "Filter",
+                "Qualify",
+                "Aggregate",
+                "Sort",
+            ],
         ),
         SqlTransform::DistinctOn(_) => contains_any(
             following,
@@ -351,6 +380,7 @@ fn is_split_required(transform: &SqlTransform, following: &mut HashSet<String>)
                 "Join",
                 "Compute",
                 "Filter",
+                "Qualify",
                 "Aggregate",
                 "Sort",
                 "Take",
@@ -364,6 +394,7 @@ fn is_split_required(transform: &SqlTransform, following: &mut HashSet<String>)
                 "Join",
                 "Compute",
                 "Filter",
+                "Qualify",
                 "Aggregate",
                 "Sort",
                 "Take",
@@ -378,6 +409,7 @@ fn is_split_required(transform: &SqlTransform, following: &mut HashSet<String>)
                 "Join",
                 "Compute",
                 "Filter",
+                "Qualify",
                 "Aggregate",
                 "Sort",
                 "Take",
@@ -454,7 +486,7 @@ pub(super) fn get_requirements(
     // special case for Compute, which contain two difference Complexity-ies
     if let Super(Compute(compute)) = transform {
         // expr itself
-        let expr_cids = CidCollector::collect(compute.expr.clone());
+        let expr_cids = CidCollector::collect(&compute.expr);
 
         let expr_max_complexity = match infer_complexity(compute) {
             // plain expressions can be included in anything less complex than Aggregation
@@ -483,18 +515,18 @@ pub(super) fn get_requirements(
 
     // general case: extract cids
     let cids = match transform {
-        Super(Compute(compute)) => CidCollector::collect(compute.expr.clone()),
-        Super(Filter(expr)) | SqlTransform::Join { filter: expr, .. } => {
-            CidCollector::collect(expr.clone())
-        }
+        Super(Compute(compute)) => CidCollector::collect(&compute.expr),
+        Super(Filter(expr))
+        | SqlTransform::Qualify(expr)
+        | SqlTransform::Join { filter: expr, .. } => CidCollector::collect(expr),
         Super(Sort(sorts)) => sorts.iter().map(|s| s.column).collect(),
         Super(Take(rq::Take { range, .. })) => {
             let mut cids = Vec::new();
             if let Some(e) = &range.start {
-                cids.extend(CidCollector::collect(e.clone()));
+                cids.extend(CidCollector::collect(e));
             }
             if let Some(e) = &range.end {
-                cids.extend(CidCollector::collect(e.clone()));
+                cids.extend(CidCollector::collect(e));
             }
             cids
         }
@@ -512,6 +544,7 @@ pub(super) fn get_requirements(
             },
             false,
         ),
+        SqlTransform::Qualify(_) => (Complexity::Aggregation, false),
         // we only use SELECTed columns in ORDER BY, so the columns can have high complexity
         Super(Sort(_)) => (Complexity::Aggregation, true),
 
@@ -556,18 +589,29 @@ pub fn infer_complexity(compute: &Compute) -> Complexity {
 }
 
 pub fn infer_complexity_expr(expr: &Expr) -> Complexity {
-    match &expr.kind {
-        rq::ExprKind::Case(_) => Complexity::NonGroup,
-        rq::ExprKind::Operator { args, .. } => args
-            .iter()
-            .map(infer_complexity_expr)
-            .max()
-            .unwrap_or(Complexity::Plain),
-        rq::ExprKind::ColumnRef(_)
-        | rq::ExprKind::Literal(_)
-        | rq::ExprKind::SString(_)
-        | rq::ExprKind::Param(_) => Complexity::Plain,
-        rq::ExprKind::Array(_) => Complexity::highest(),
+    let mut inference = ComplexityInference {
+        complexity: Complexity::Plain,
+    };
+    inference.visit_expr(expr);
+    inference.complexity
+}
+
+struct ComplexityInference {
+    complexity: Complexity,
+}
+
+impl RqVisitor for ComplexityInference {
+    fn visit_expr(&mut self, expr: &Expr) {
+        let complexity = match &expr.kind {
+            rq::ExprKind::Case(_) => Complexity::NonGroup,
+            rq::ExprKind::Operator { .. } => return visit_expr_kind(self, &expr.kind),
+            rq::ExprKind::ColumnRef(_)
+            | rq::ExprKind::Literal(_)
+            | rq::ExprKind::SString(_)
+            | rq::ExprKind::Param(_) => Complexity::Plain,
+            rq::ExprKind::Array(_) => Complexity::highest(),
+        };
+        self.complexity = self.complexity.max(complexity);
     }
 }
 
@@ -579,23 +623,22 @@ pub struct CidCollector {
 }
 
 impl CidCollector {
-    pub fn collect(expr: Expr) -> Vec<CId> {
+    pub fn collect(expr: &Expr) -> Vec<CId> {
         let mut collector = CidCollector::default();
-        collector.fold_expr(expr).unwrap();
+        collector.visit_expr(expr);
         collector.cids
     }
 
-    pub fn collect_t(t: Transform) -> (Transform, Vec<CId>) {
+    pub fn collect_t(t: &Transform) -> Vec<CId> {
         let mut collector = CidCollector::default();
-        let t = collector.fold_transform(t).unwrap();
-        (t, collector.cids)
+        collector.visit_transform(t);
+        collector.cids
     }
 }
 
-impl RqFold for CidCollector {
-    fn fold_cid(&mut self, cid: CId) -> Result<CId> {
+impl RqVisitor for CidCollector {
+    fn visit_cid(&mut self, cid: CId) {
         self.cids.push(cid);
-        Ok(cid)
     }
 }
 
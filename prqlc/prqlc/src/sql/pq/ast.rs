@@ -86,6 +86,8 @@ pub enum SqlTransform<Rel = RIId, Super = rq::Transform> {
     From(Rel),
     Select(Vec<rq::CId>),
     Filter(rq::Expr),
+    /// Filter by window functions, which is applied after they are computed.
+    Qualify(rq::Expr),
     Aggregate {
         partition: Vec<rq::CId>,
         compute: Vec<rq::CId>,
@@ -190,6 +192,7 @@ pub fn fold_sql_transform<
         },
         SqlTransform::Select(v) => SqlTransform::Select(fold.fold_cids(v)?),
         SqlTransform::Filter(v) => SqlTransform::Filter(fold.fold_expr(v)?),
+        SqlTransform::Qualify(v) => SqlTransform::Qualify(fold.fold_expr(v)?),
         SqlTransform::Aggregate { partition, compute } => SqlTransform::Aggregate {
             partition: fold.fold_cids(partition)?,
             compute: fold.fold_cids(compute)?,
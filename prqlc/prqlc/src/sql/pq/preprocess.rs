@@ -71,14 +71,10 @@ pub(in crate::sql) fn prune_inputs(
         // collect cids (special case for Join & From)
         match transform {
             SqlTransform::Join { ref filter, .. } => {
-                used_cids.extend(CidCollector::collect(filter.clone()));
+                used_cids.extend(CidCollector::collect(filter));
             }
             SqlTransform::From(_) => {}
-            Super(t) => {
-                let (t, cids) = CidCollector::collect_t(t);
-                used_cids.extend(cids);
-                transform = Super(t);
-            }
+            Super(ref t) => used_cids.extend(CidCollector::collect_t(t)),
             _ => unreachable!(),
         }
 
@@ -247,7 +243,7 @@ fn create_filter_by_row_number(
     let range_int = range.try_map(as_int).unwrap();
 
     let compute = SqlTransform::Super(Transform::Compute(compute));
-    let filter = SqlTransform::Super(Transform::Filter(match (range_int.start, range_int.end) {
+    let condition = match (range_int.start, range_int.end) {
         (Some(s), Some(e)) if s == e => new_binop(col_ref, "std.eq", int_expr(s)),
         (start, end) => {
             let start = start.map(|start| new_binop(col_ref.clone(), "std.gte", int_expr(start)));
@@ -258,7 +254,12 @@ fn create_filter_by_row_number(
                 span: None,
             })
         }
-    }));
+    };
+    let filter = if ctx.dialect.supports_qualify() {
+        SqlTransform::Qualify(condition)
+    } else {
+        SqlTransform::Super(Transform::Filter(condition))
+    };
 
     vec![compute, filter]
 }
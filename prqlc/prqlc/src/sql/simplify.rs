@@ -0,0 +1,111 @@
+//! Simplification of RQ expressions, so the generated SQL is shorter.
+//!
+//! Applied unless [Options::simplify](crate::Options::simplify) is disabled.
+
+use crate::debug;
+use crate::ir::rq::{self, RqFold};
+use crate::lr::Literal;
+use crate::Result;
+
+/// Folds constant integer arithmetic, removes double negations and
+/// `filter true`, and merges adjacent filters.
+pub(super) fn simplify(query: rq::RelationalQuery) -> Result<rq::RelationalQuery> {
+    debug::log_stage(debug::Stage::Sql(debug::StageSql::Simplify));
+
+    let query = Simplifier.fold_query(query)?;
+    debug::log_entry(|| debug::DebugEntryKind::ReprRq(query.clone()));
+    Ok(query)
+}
+
+struct Simplifier;
+
+impl RqFold for Simplifier {
+    fn fold_transforms(&mut self, transforms: Vec<rq::Transform>) -> Result<Vec<rq::Transform>> {
+        let transforms = rq::fold_transforms(self, transforms)?;
+
+        let mut res: Vec<rq::Transform> = Vec::with_capacity(transforms.len());
+        for transform in transforms {
+            match transform {
+                rq::Transform::Filter(filter) if is_literal_true(&filter) => {}
+                rq::Transform::Filter(filter) => match res.pop() {
+                    Some(rq::Transform::Filter(prev)) => res.push(rq::Transform::Filter(
+                        rq::new_binop(prev, "std.and", filter),
+                    )),
+                    prev => {
+                        res.extend(prev);
+                        res.push(rq::Transform::Filter(filter));
+                    }
+                },
+                transform => res.push(transform),
+            }
+        }
+        Ok(res)
+    }
+
+    fn fold_expr_kind(&mut self, kind: rq::ExprKind) -> Result<rq::ExprKind> {
+        let kind = rq::fold_expr_kind(self, kind)?;
+
+        let rq::ExprKind::Operator { name, mut args } = kind else {
+            return Ok(kind);
+        };
+
+        let simplified = match (name.as_str(), args.as_mut_slice()) {
+            ("std.neg" | "std.not", [arg]) => match &mut arg.kind {
+                rq::ExprKind::Operator {
+                    name: inner,
+                    args: inner_args,
+                } if *inner == name && inner_args.len() == 1 => Some(inner_args.remove(0).kind),
+                rq::ExprKind::Literal(Literal::Integer(i)) if name == "std.neg" => i
+                    .checked_neg()
+                    .map(|i| rq::ExprKind::Literal(Literal::Integer(i))),
+                rq::ExprKind::Literal(Literal::Boolean(b)) if name == "std.not" => {
+                    Some(rq::ExprKind::Literal(Literal::Boolean(!*b)))
+                }
+                _ => None,
+            },
+
+            ("std.add" | "std.sub" | "std.mul", [left, right]) => match (&left.kind, &right.kind) {
+                (
+                    rq::ExprKind::Literal(Literal::Integer(l)),
+                    rq::ExprKind::Literal(Literal::Integer(r)),
+                ) => match name.as_str() {
+                    "std.add" => l.checked_add(*r),
+                    "std.sub" => l.checked_sub(*r),
+                    _ => l.checked_mul(*r),
+                }
+                .map(|i| rq::ExprKind::Literal(Literal::Integer(i))),
+                _ => None,
+            },
+
+            // `x && true` is `x`, even when `x` is null; the same goes for `x || false`
+            ("std.and" | "std.or", [left, right]) => {
+                let neutral = name == "std.and";
+                if is_literal_bool(right, neutral) {
+                    Some(std::mem::replace(
+                        &mut left.kind,
+                        rq::ExprKind::Literal(Literal::Null),
+                    ))
+                } else if is_literal_bool(left, neutral) {
+                    Some(std::mem::replace(
+                        &mut right.kind,
+                        rq::ExprKind::Literal(Literal::Null),
+                    ))
+                } else {
+                    None
+                }
+            }
+
+            _ => None,
+        };
+
+        Ok(simplified.unwrap_or(rq::ExprKind::Operator { name, args }))
+    }
+}
+
+fn is_literal_bool(expr: &rq::Expr, value: bool) -> bool {
+    matches!(expr.kind, rq::ExprKind::Literal(Literal::Boolean(b)) if b == value)
+}
+
+fn is_literal_true(expr: &rq::Expr) -> bool {
+    is_literal_bool(expr, true)
+}
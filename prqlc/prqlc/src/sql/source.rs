@@ -0,0 +1,55 @@
+//! Resolution of tables that are referenced by a query, but not defined in it.
+
+use crate::ir::pl::TableExternRef;
+use crate::ir::rq;
+use crate::pr::Ident;
+use crate::Result;
+
+/// Decides what a reference to an external table compiles to.
+///
+/// Embedders can implement this to validate table names against a schema,
+/// to redirect tables, or to provide virtual tables.
+pub trait SourceResolver {
+    fn resolve_source(&self, name: &Ident) -> Result<ResolvedSource>;
+}
+
+/// What a reference to an external table is replaced with.
+#[derive(Debug, Clone)]
+pub enum ResolvedSource {
+    /// A table in the database, referenced by name.
+    Table(Ident),
+
+    /// A relation defined within the query, which is compiled into a CTE.
+    /// It must provide the columns that the query uses.
+    Relation(rq::RelationKind),
+}
+
+/// Refers to every table by the name used in the query.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultSourceResolver;
+
+impl SourceResolver for DefaultSourceResolver {
+    fn resolve_source(&self, name: &Ident) -> Result<ResolvedSource> {
+        Ok(ResolvedSource::Table(name.clone()))
+    }
+}
+
+pub(super) fn resolve_sources(
+    mut query: rq::RelationalQuery,
+    resolver: &dyn SourceResolver,
+) -> Result<rq::RelationalQuery> {
+    for table in &mut query.tables {
+        let rq::RelationKind::ExternRef(TableExternRef::LocalTable(name)) = &table.relation.kind
+        else {
+            continue;
+        };
+
+        table.relation.kind = match resolver.resolve_source(name)? {
+            ResolvedSource::Table(name) => {
+                rq::RelationKind::ExternRef(TableExternRef::LocalTable(name))
+            }
+            ResolvedSource::Relation(kind) => kind,
+        };
+    }
+    Ok(query)
+}
@@ -0,0 +1,256 @@
+//! Rendering of the generated SQL AST into text.
+
+use itertools::Itertools;
+use sqlparser::ast::{
+    Expr, Fetch, GroupByExpr, Offset, OrderBy, Query, Select, SetExpr, SetQuantifier,
+    TableWithJoins, With,
+};
+
+/// Renders the SQL AST that the compiler generates, one clause at a time.
+///
+/// Embedders can implement this to adjust the rendering of a clause, such as
+/// for an experimental dialect, and use the default methods for the rest. The
+/// default methods produce the same text as sqlparser's `Display`.
+///
+/// Queries within expressions and within `FROM` are rendered with `Display`,
+/// as are queries and `SELECT`s that have clauses the compiler doesn't
+/// generate, so that none of them are dropped.
+pub trait SqlWriter {
+    fn write_query(&self, query: &Query) -> String {
+        write_query(self, query)
+    }
+    fn write_with(&self, with: &With) -> String {
+        write_with(self, with)
+    }
+    fn write_set_expr(&self, body: &SetExpr) -> String {
+        write_set_expr(self, body)
+    }
+    fn write_select(&self, select: &Select) -> String {
+        write_select(self, select)
+    }
+    fn write_from(&self, from: &[TableWithJoins]) -> String {
+        format!("FROM {}", from.iter().join(", "))
+    }
+    fn write_where(&self, selection: &Expr) -> String {
+        format!("WHERE {selection}")
+    }
+    fn write_group_by(&self, group_by: &GroupByExpr) -> String {
+        group_by.to_string()
+    }
+    fn write_having(&self, having: &Expr) -> String {
+        format!("HAVING {having}")
+    }
+    fn write_qualify(&self, qualify: &Expr) -> String {
+        format!("QUALIFY {qualify}")
+    }
+    fn write_order_by(&self, order_by: &OrderBy) -> String {
+        order_by.to_string()
+    }
+    fn write_limit(&self, limit: &Expr) -> String {
+        format!("LIMIT {limit}")
+    }
+    fn write_offset(&self, offset: &Offset) -> String {
+        offset.to_string()
+    }
+    fn write_fetch(&self, fetch: &Fetch) -> String {
+        fetch.to_string()
+    }
+}
+
+/// Renders the SQL AST with sqlparser's `Display`, without any changes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultSqlWriter;
+
+impl SqlWriter for DefaultSqlWriter {
+    fn write_query(&self, query: &Query) -> String {
+        query.to_string()
+    }
+}
+
+pub fn write_query<W: ?Sized + SqlWriter>(writer: &W, query: &Query) -> String {
+    let has_other_clauses = !query.limit_by.is_empty()
+        || !query.locks.is_empty()
+        || query.for_clause.is_some()
+        || query.settings.is_some()
+        || query.format_clause.is_some();
+    if has_other_clauses {
+        return query.to_string();
+    }
+
+    let mut parts = Vec::new();
+    if let Some(with) = &query.with {
+        parts.push(writer.write_with(with));
+    }
+    parts.push(writer.write_set_expr(&query.body));
+    if let Some(order_by) = &query.order_by {
+        parts.push(writer.write_order_by(order_by));
+    }
+    if let Some(limit) = &query.limit {
+        parts.push(writer.write_limit(limit));
+    }
+    if let Some(offset) = &query.offset {
+        parts.push(writer.write_offset(offset));
+    }
+    if let Some(fetch) = &query.fetch {
+        parts.push(writer.write_fetch(fetch));
+    }
+    parts.join(" ")
+}
+
+pub fn write_with<W: ?Sized + SqlWriter>(writer: &W, with: &With) -> String {
+    let ctes = with
+        .cte_tables
+        .iter()
+        .map(|cte| format!("{} AS ({})", cte.alias, writer.write_query(&cte.query)))
+        .join(", ");
+    let recursive = if with.recursive { "RECURSIVE " } else { "" };
+    format!("WITH {recursive}{ctes}")
+}
+
+pub fn write_set_expr<W: ?Sized + SqlWriter>(writer: &W, body: &SetExpr) -> String {
+    match body {
+        SetExpr::Select(select) => writer.write_select(select),
+        SetExpr::Query(query) => format!("({})", writer.write_query(query)),
+        SetExpr::SetOperation {
+            left,
+            right,
+            op,
+            set_quantifier,
+        } => {
+            let quantifier = match set_quantifier {
+                SetQuantifier::None => String::new(),
+                quantifier => format!(" {quantifier}"),
+            };
+            format!(
+                "{} {op}{quantifier} {}",
+                writer.write_set_expr(left),
+                writer.write_set_expr(right)
+            )
+        }
+        body => body.to_string(),
+    }
+}
+
+pub fn write_select<W: ?Sized + SqlWriter>(writer: &W, select: &Select) -> String {
+    let has_other_clauses = select.into.is_some()
+        || !select.lateral_views.is_empty()
+        || select.prewhere.is_some()
+        || !select.cluster_by.is_empty()
+        || !select.distribute_by.is_empty()
+        || !select.sort_by.is_empty()
+        || !select.named_window.is_empty()
+        || select.value_table_mode.is_some()
+        || select.connect_by.is_some();
+    if has_other_clauses {
+        return select.to_string();
+    }
+
+    let mut parts = vec!["SELECT".to_string()];
+    if let Some(distinct) = &select.distinct {
+        parts.push(distinct.to_string());
+    }
+    if let Some(top) = &select.top {
+        parts.push(top.to_string());
+    }
+    parts.push(select.projection.iter().join(", "));
+    if !select.from.is_empty() {
+        parts.push(writer.write_from(&select.from));
+    }
+    if let Some(selection) = &select.selection {
+        parts.push(writer.write_where(selection));
+    }
+    let has_group_by = match &select.group_by {
+        GroupByExpr::All(_) => true,
+        GroupByExpr::Expressions(exprs, _) => !exprs.is_empty(),
+    };
+    if has_group_by {
+        parts.push(writer.write_group_by(&select.group_by));
+    }
+    if let Some(having) = &select.having {
+        parts.push(writer.write_having(having));
+    }
+    if let Some(qualify) = &select.qualify {
+        parts.push(writer.write_qualify(qualify));
+    }
+    parts.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+    use sqlparser::ast::Statement;
+    use sqlparser::dialect::{ClickHouseDialect, GenericDialect};
+    use sqlparser::parser::Parser;
+
+    use super::*;
+    use crate::sql::DefaultSourceResolver;
+    use crate::{ErrorMessages, Options};
+
+    struct FetchFirst;
+
+    impl SqlWriter for FetchFirst {
+        fn write_limit(&self, limit: &Expr) -> String {
+            format!("FETCH FIRST {limit} ROWS ONLY")
+        }
+    }
+
+    #[test]
+    fn test_sql_writer() {
+        let compile_with_writer = |prql: &str| {
+            let rq = crate::prql_to_pl(prql).and_then(crate::pl_to_rq)?;
+            let opts = Options::default().no_signature();
+            crate::sql::compile_with(vec![rq], &opts, &DefaultSourceResolver, &FetchFirst)
+                .map_err(ErrorMessages::from)
+        };
+
+        // the writer is also used for the queries of CTEs
+        assert_snapshot!(compile_with_writer(r###"
+        from employees
+        take 10
+        join departments (==dept_id)
+        sort employees.name
+        take 5
+        "###).unwrap(), @r"
+        WITH table_0 AS (
+          SELECT
+            *
+          FROM
+            employees
+          FETCH FIRST
+            10 ROWS ONLY
+        )
+        SELECT
+          table_0.*,
+          departments.*
+        FROM
+          table_0
+          JOIN departments ON table_0.dept_id = departments.dept_id
+        ORDER BY
+          table_0.name
+        FETCH FIRST
+          5 ROWS ONLY
+        ");
+    }
+
+    #[test]
+    fn test_other_clauses() {
+        // clauses that the compiler doesn't generate are kept as they are
+        for (dialect, sql) in [
+            (
+                &ClickHouseDialect {} as &dyn sqlparser::dialect::Dialect,
+                "SELECT a FROM t LIMIT 1 BY a SETTINGS max_threads = 1",
+            ),
+            (&GenericDialect {}, "SELECT a INTO b FROM t"),
+            (
+                &GenericDialect {},
+                "SELECT a FROM t WINDOW w AS (ORDER BY a)",
+            ),
+        ] {
+            let Statement::Query(query) = Parser::parse_sql(dialect, sql).unwrap().remove(0) else {
+                panic!("not a query: {sql}")
+            };
+            assert_eq!(FetchFirst.write_query(&query), query.to_string());
+            assert_eq!(DefaultSqlWriter.write_query(&query), query.to_string());
+        }
+    }
+}
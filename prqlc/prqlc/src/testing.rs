@@ -0,0 +1,361 @@
+//! Utilities for testing the compiler with generated queries, and with
+//! directories of queries. Requires the `test-utils` feature.
+//!
+//! [QueryGenerator] produces random, but valid, PRQL queries from a seed, so
+//! failures can be reproduced. The `assert_*` functions check invariants that
+//! should hold for every query, and panic with the query when they don't:
+//!
+//! ```
+//! use prqlc::sql::Dialect;
+//! use prqlc::testing::{assert_format_round_trip, assert_sql_parses, QueryGenerator};
+//!
+//! for seed in 0..10 {
+//!     let prql = QueryGenerator::new(seed).query();
+//!
+//!     assert_format_round_trip(&prql);
+//!     assert_sql_parses(&prql, Dialect::Postgres);
+//! }
+//! ```
+//!
+//! [assert_sql_snapshots] compiles each query of a directory and compares it
+//! with the SQL checked in next to it, so a corpus of queries can catch
+//! changes in the SQL when upgrading the compiler.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::sql::Dialect;
+use crate::{compile, pl_to_prql, prql_to_pl, Options, Target};
+
+/// Generates random PRQL queries.
+///
+/// Queries read from tables with unknown columns, and use a subset of the
+/// language: `derive`, `filter`, `select`, `sort`, `take`, `aggregate` and
+/// `group`. Each query is valid, so it should always compile.
+#[derive(Debug, Clone)]
+pub struct QueryGenerator {
+    state: u64,
+
+    /// Columns of the current relation, or None while it has all columns of
+    /// the table, which are unknown.
+    columns: Option<Vec<String>>,
+    derived: Vec<String>,
+    names: usize,
+}
+
+const TABLE_COLUMNS: &[&str] = &["a", "b", "c", "d"];
+
+impl QueryGenerator {
+    /// Creates a generator; the same seed produces the same queries.
+    pub fn new(seed: u64) -> Self {
+        QueryGenerator {
+            // xorshift requires a non-zero state
+            state: seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1,
+            columns: None,
+            derived: Vec::new(),
+            names: 0,
+        }
+    }
+
+    /// Generates the next query.
+    pub fn query(&mut self) -> String {
+        self.columns = None;
+        self.derived.clear();
+        self.names = 0;
+
+        let mut lines = vec![format!("from t{}", self.below(3))];
+        for _ in 0..=self.below(5) {
+            lines.push(self.transform());
+        }
+        lines.join("\n") + "\n"
+    }
+
+    fn transform(&mut self) -> String {
+        match self.below(8) {
+            0 | 1 => {
+                let name = self.new_name();
+                let expr = self.expr(2);
+                self.add_column(name.clone());
+                format!("derive {{{name} = {expr}}}")
+            }
+            2 | 3 => format!("filter {}", self.condition(2)),
+            4 => {
+                let mut columns = Vec::new();
+                for _ in 0..=self.below(2) {
+                    let column = self.column();
+                    if !columns.contains(&column) {
+                        columns.push(column);
+                    }
+                }
+                let select = format!("select {{{}}}", columns.join(", "));
+                self.columns = Some(columns);
+                select
+            }
+            5 => {
+                let direction = if self.below(2) == 0 { "" } else { "-" };
+                format!("sort {{{direction}{}}}", self.column())
+            }
+            6 => match self.below(2) {
+                0 => format!("take {}", self.below(20) + 1),
+                _ => {
+                    let start = self.below(10) + 1;
+                    format!("take {start}..{}", start + self.below(10))
+                }
+            },
+            _ => {
+                let by = (self.below(2) == 0).then(|| self.column());
+                let name = self.new_name();
+                let func = ["sum", "min", "max", "average"][self.below(4)];
+                let column = self.column();
+
+                // the columns of a group aren't available within it
+                let value = if by.as_ref() == Some(&column) {
+                    "count this".to_string()
+                } else {
+                    format!("{func} {column}")
+                };
+                let aggregate = format!("aggregate {{{name} = {value}}}");
+
+                if let Some(by) = by {
+                    self.columns = Some(vec![by.clone(), name]);
+                    format!("group {{{by}}} ({aggregate})")
+                } else {
+                    self.columns = Some(vec![name]);
+                    aggregate
+                }
+            }
+        }
+    }
+
+    fn expr(&mut self, depth: usize) -> String {
+        match self.below(if depth == 0 { 2 } else { 4 }) {
+            0 => self.column(),
+            1 => self.below(100).to_string(),
+            _ => {
+                let op = ["+", "-", "*"][self.below(3)];
+                format!("({} {op} {})", self.expr(depth - 1), self.expr(depth - 1))
+            }
+        }
+    }
+
+    fn condition(&mut self, depth: usize) -> String {
+        match self.below(if depth == 0 { 2 } else { 3 }) {
+            0 => {
+                let op = ["==", "!=", ">", "<=", ">="][self.below(5)];
+                format!("{} {op} {}", self.expr(1), self.expr(1))
+            }
+            1 => format!("{} == null", self.column()),
+            _ => {
+                let op = ["&&", "||"][self.below(2)];
+                let left = self.condition(depth - 1);
+                let right = self.condition(depth - 1);
+                format!("({left} {op} {right})")
+            }
+        }
+    }
+
+    fn column(&mut self) -> String {
+        if let Some(len) = self.columns.as_ref().map(Vec::len) {
+            let index = self.below(len);
+            return self.columns.as_ref().unwrap()[index].clone();
+        }
+
+        let count = TABLE_COLUMNS.len() + self.derived.len();
+        match self.below(count) {
+            i if i < TABLE_COLUMNS.len() => TABLE_COLUMNS[i].to_string(),
+            i => self.derived[i - TABLE_COLUMNS.len()].clone(),
+        }
+    }
+
+    fn add_column(&mut self, name: String) {
+        if let Some(columns) = &mut self.columns {
+            columns.push(name.clone());
+        }
+        self.derived.push(name);
+    }
+
+    fn new_name(&mut self) -> String {
+        self.names += 1;
+        format!("x{}", self.names)
+    }
+
+    /// A random number in `0..n`.
+    fn below(&mut self, n: usize) -> usize {
+        // xorshift64*
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        let random = self.state.wrapping_mul(0x2545_F491_4F6C_DD1D);
+        (random >> 33) as usize % n
+    }
+}
+
+/// Asserts that formatting a query is stable: parsing and formatting the
+/// formatted query produces it again.
+pub fn assert_format_round_trip(prql: &str) {
+    let format = |prql: &str| {
+        let pl = prql_to_pl(prql).unwrap_or_else(|e| panic!("cannot parse:\n{prql}\n{e}"));
+        pl_to_prql(&pl).unwrap_or_else(|e| panic!("cannot format:\n{prql}\n{e}"))
+    };
+
+    let formatted = format(prql);
+    let reformatted = format(&formatted);
+    assert_eq!(
+        formatted, reformatted,
+        "formatting is not stable for:\n{prql}"
+    );
+}
+
+/// Asserts that a query compiles for a dialect, and that the SQL is accepted
+/// by the parser of [sqlparser] for that dialect. Returns the SQL.
+pub fn assert_sql_parses(prql: &str, dialect: Dialect) -> String {
+    let options = Options::default()
+        .no_signature()
+        .with_target(Target::Sql(Some(dialect)));
+    let sql = compile(prql, &options)
+        .unwrap_or_else(|e| panic!("cannot compile for {dialect}:\n{prql}\n{e}"));
+
+    let parser_dialect = dialect.parser_dialect();
+    if let Err(e) = sqlparser::parser::Parser::parse_sql(parser_dialect.as_ref(), &sql) {
+        panic!("SQL for {dialect} does not parse: {e}\n{prql}\n{sql}");
+    }
+    sql
+}
+
+/// Asserts that each `.prql` file within a directory (including its
+/// subdirectories) compiles to the SQL of the `.sql` file next to it.
+///
+/// When the `UPDATE_SNAPSHOTS` environment variable is set, the `.sql` files
+/// are written instead. All mismatches are reported in a single panic.
+///
+/// ```no_run
+/// use prqlc::Options;
+/// use prqlc::testing::assert_sql_snapshots;
+///
+/// assert_sql_snapshots("queries", &Options::default().no_signature());
+/// ```
+pub fn assert_sql_snapshots(dir: impl AsRef<Path>, options: &Options) {
+    let update = std::env::var_os("UPDATE_SNAPSHOTS").map_or(false, |v| !v.is_empty() && v != "0");
+
+    if let Err(message) = check_sql_snapshots(dir.as_ref(), options, update) {
+        panic!("{message}");
+    }
+}
+
+fn check_sql_snapshots(dir: &Path, options: &Options, update: bool) -> Result<(), String> {
+    let mut paths = Vec::new();
+    find_prql_files(dir, &mut paths).map_err(|e| format!("cannot read {}: {e}", dir.display()))?;
+    paths.sort();
+
+    let mut failures = Vec::new();
+    for prql_path in paths {
+        let sql_path = prql_path.with_extension("sql");
+
+        let prql = match fs::read_to_string(&prql_path) {
+            Ok(prql) => prql,
+            Err(e) => {
+                failures.push(format!("cannot read {}: {e}", prql_path.display()));
+                continue;
+            }
+        };
+        let mut sql = match compile(&prql, options) {
+            Ok(sql) => sql,
+            Err(e) => {
+                failures.push(format!("cannot compile {}:\n{e}", prql_path.display()));
+                continue;
+            }
+        };
+        if !sql.ends_with('\n') {
+            sql.push('\n');
+        }
+
+        if update {
+            if let Err(e) = fs::write(&sql_path, &sql) {
+                failures.push(format!("cannot write {}: {e}", sql_path.display()));
+            }
+            continue;
+        }
+
+        match fs::read_to_string(&sql_path) {
+            Ok(expected) if expected.trim_end() == sql.trim_end() => {}
+            Ok(expected) => failures.push(format!(
+                "SQL of {} has changed\n--- expected\n{expected}\n--- actual\n{sql}",
+                prql_path.display()
+            )),
+            Err(_) => failures.push(format!("missing snapshot {}", sql_path.display())),
+        }
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        failures.push("set UPDATE_SNAPSHOTS=1 to update the snapshots".to_string());
+        Err(failures.join("\n\n"))
+    }
+}
+
+fn find_prql_files(dir: &Path, paths: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_prql_files(&path, paths)?;
+        } else if path.extension().map_or(false, |e| e == "prql") {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_queries() {
+        let mut generator = QueryGenerator::new(0);
+
+        for _ in 0..200 {
+            let prql = generator.query();
+
+            assert_format_round_trip(&prql);
+            for dialect in [Dialect::Generic, Dialect::Postgres, Dialect::SQLite] {
+                assert_sql_parses(&prql, dialect);
+            }
+        }
+    }
+
+    #[test]
+    fn test_generator_is_deterministic() {
+        let queries = |seed| {
+            let mut generator = QueryGenerator::new(seed);
+            (0..5).map(|_| generator.query()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(queries(42), queries(42));
+        assert_ne!(queries(42), queries(43));
+    }
+
+    #[test]
+    fn test_sql_snapshots() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("nested")).unwrap();
+        fs::write(dir.path().join("a.prql"), "from a").unwrap();
+        fs::write(dir.path().join("nested/b.prql"), "from b | take 1").unwrap();
+        let options = Options::default().no_signature().no_format();
+
+        let err = check_sql_snapshots(dir.path(), &options, false).unwrap_err();
+        assert!(err.contains("missing snapshot"));
+
+        check_sql_snapshots(dir.path(), &options, true).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("nested/b.sql")).unwrap(),
+            "SELECT * FROM b LIMIT 1\n"
+        );
+        check_sql_snapshots(dir.path(), &options, false).unwrap();
+
+        fs::write(dir.path().join("a.prql"), "from c").unwrap();
+        let err = check_sql_snapshots(dir.path(), &options, false).unwrap_err();
+        assert!(err.contains("has changed"));
+        assert!(!err.contains("b.prql"));
+    }
+}
@@ -141,24 +141,6 @@ fn invalid_lineage_in_transform() {
     ");
 }
 
-#[test]
-fn test_hint_missing_args() {
-    assert_snapshot!(compile(r###"
-    from film
-    select {film_id, lag film_id}
-    "###).unwrap_err(), @r"
-    Error:
-       ╭─[:3:22]
-       │
-     3 │     select {film_id, lag film_id}
-       │                      ─────┬─────
-       │                           ╰─────── unexpected `(func offset <int> column <array> -> internal std.lag) film_id`
-       │
-       │ Help: this is probably a 'bad type' error (we are working on that)
-    ───╯
-    ")
-}
-
 #[test]
 fn test_relation_literal_contains_literals() {
     assert_snapshot!(compile(r###"
@@ -328,6 +328,27 @@ fn date_to_text_with_column_format() {
     ");
 }
 
+#[test]
+fn date_to_char_unsupported_pattern() {
+    assert_snapshot!(compile(r#"
+  prql target:sql.postgres
+
+  from [{d = @2021-01-01}]
+  derive {
+    d_str = (d | date.to_char "YYYY Q")
+  }"#).unwrap_err(), @r#"
+    Error:
+       ╭─[:6:31]
+       │
+     6 │     d_str = (d | date.to_char "YYYY Q")
+       │                               ────┬───
+       │                                   ╰───── unsupported date format pattern at `Q`
+       │
+       │ Help: text can be output as is by wrapping it in double quotes
+    ───╯
+    "#);
+}
+
 #[test]
 fn date_to_text_unsupported_chrono_item() {
     assert_snapshot!(compile(r#"
@@ -366,6 +387,26 @@ fn available_columns() {
     ");
 }
 
+#[test]
+fn available_columns_after_aggregate() {
+    // only the group keys and the aggregated columns remain after an aggregate
+    assert_snapshot!(compile(r#"
+    from sales
+    group country (aggregate {total = sum amount})
+    sort city
+    "#).unwrap_err(), @r"
+    Error:
+       ╭─[:4:10]
+       │
+     4 │     sort city
+       │          ──┬─
+       │            ╰─── Unknown name `city`
+       │
+       │ Help: available columns: sales.country, total
+    ───╯
+    ");
+}
+
 #[test]
 fn empty_interpolations() {
     assert_snapshot!(compile(r#"from x | select f"{}" "#).unwrap_err(), @r#"
@@ -378,3 +419,313 @@ fn empty_interpolations() {
     ───╯
     "#);
 }
+
+#[test]
+fn derive_existing_column() {
+    assert_snapshot!(compile(r###"
+    from employees
+    select {first_name, salary}
+    derive salary = 100
+    "###).unwrap_err(), @r"
+    Error:
+       ╭─[:4:21]
+       │
+     4 │     derive salary = 100
+       │                     ─┬─
+       │                      ╰─── column `salary` is already defined
+       │
+       │ Help: use a different name or replace the column with `select`
+    ───╯
+    ");
+
+    assert_snapshot!(compile(r###"
+    from employees
+    derive {bonus = salary * 0.1, bonus = 100}
+    "###).unwrap_err(), @r"
+    Error:
+       ╭─[:3:43]
+       │
+     3 │     derive {bonus = salary * 0.1, bonus = 100}
+       │                                           ─┬─
+       │                                            ╰─── column `bonus` is already defined
+       │
+       │ Help: use a different name or replace the column with `select`
+    ───╯
+    ");
+
+    // redefining a column in terms of itself is fine
+    assert_snapshot!(compile(r###"
+    from employees
+    select {first_name, salary}
+    derive salary = salary + 100
+    "###).unwrap(), @r"
+    SELECT
+      first_name,
+      salary AS _expr_0,
+      salary + 100 AS salary
+    FROM
+      employees
+    ");
+}
+
+#[test]
+fn derive_forward_reference() {
+    assert_snapshot!(compile(r###"
+    from employees
+    select {salary, tax}
+    derive {gross = net + tax, net = salary * 0.7}
+    "###).unwrap_err(), @r"
+    Error:
+       ╭─[:4:21]
+       │
+     4 │     derive {gross = net + tax, net = salary * 0.7}
+       │                     ─┬─
+       │                      ╰─── `net` is used before it is defined
+       │
+       │ Help: move `net` before its use, or define it in an earlier transform
+    ───╯
+    ");
+}
+
+#[test]
+fn greatest_null_safe_not_literal() {
+    assert_snapshot!(compile(r###"
+    from employees
+    derive {x = greatest salary bonus null_safe:is_manager}
+    "###).unwrap_err(), @r"
+    Error:
+       ╭─[:3:49]
+       │
+     3 │     derive {x = greatest salary bonus null_safe:is_manager}
+       │                                                 ─────┬────
+       │                                                      ╰────── `null_safe` must be a boolean literal
+    ───╯
+    ");
+}
+
+#[test]
+fn unknown_named_argument() {
+    assert_snapshot!(compile(r###"
+    from employees
+    sort descending:true salary
+    "###).unwrap_err(), @r"
+    Error:
+       ╭─[:3:21]
+       │
+     3 │     sort descending:true salary
+       │                     ──┬─
+       │                       ╰─── Unknown named argument `descending` to function `sort`
+    ───╯
+    ");
+}
+
+#[test]
+fn misspelled_column() {
+    assert_snapshot!(compile(r###"
+    from invoices
+    select {amount, customer_id}
+    filter amout > 100
+    "###).unwrap_err(), @r"
+    Error:
+       ╭─[:4:12]
+       │
+     4 │     filter amout > 100
+       │            ──┬──
+       │              ╰──── Unknown name `amout`
+       │
+       │ Help: did you mean `invoices.amount`?
+       │
+       │ Note: available columns: invoices.amount, invoices.customer_id
+    ───╯
+    ");
+}
+
+#[test]
+fn lints() {
+    use prqlc::lint::Lint;
+
+    let prql = r#"
+    from employees
+    derive {bonus = salary * 0.1, gross = salary + benefits}
+    select {name, gross}
+    take 10
+    "#;
+
+    let messages = prqlc::lint(prql, &Lint::all()).unwrap();
+    let found: Vec<_> = (messages.inner.iter())
+        .map(|m| (m.code.as_deref().unwrap(), m.reason.as_str()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("L001", "this column is computed, but never used"),
+            (
+                "L002",
+                "`take` of an unsorted relation returns arbitrary rows"
+            ),
+        ]
+    );
+    assert!(messages
+        .inner
+        .iter()
+        .all(|m| m.kind == prqlc::MessageKind::Lint && m.location.is_some()));
+
+    let messages = prqlc::lint(prql, &[Lint::UnsortedTake]).unwrap();
+    assert_eq!(messages.inner.len(), 1);
+
+    let sorted = "from employees | sort salary | take 10";
+    assert!(prqlc::lint(sorted, &Lint::all()).unwrap().inner.is_empty());
+}
+
+#[test]
+fn lint_outer_join_filter() {
+    use prqlc::lint::Lint;
+
+    // a filter on the right side of a left join discards unmatched rows
+    let messages = prqlc::lint(
+        r#"
+    from e = employees
+    join side:left d = departments (e.dept_id == d.id)
+    filter d.active == true
+    "#,
+        &[Lint::OuterJoinFilter],
+    )
+    .unwrap();
+    assert_snapshot!(messages, @r"
+    [L003] Lint:
+       ╭─[:4:12]
+       │
+     4 │     filter d.active == true
+       │            ────────┬───────
+       │                    ╰───────── this filter removes the rows where `d` had no match, as an inner join would
+       │
+       │ Help: move the condition into the join to keep them
+    ───╯
+    ");
+
+    // filters on the left side, or that check for nulls, are fine
+    let messages = prqlc::lint(
+        r#"
+    from e = employees
+    join side:left d = departments (e.dept_id == d.id)
+    filter e.salary > 1000
+    filter d.id == null || d.active
+    "#,
+        &[Lint::OuterJoinFilter],
+    )
+    .unwrap();
+    assert!(messages.inner.is_empty());
+
+    // as are filters after an inner join
+    let messages = prqlc::lint(
+        r#"
+    from e = employees
+    join d = departments (e.dept_id == d.id)
+    filter d.active == true
+    "#,
+        &[Lint::OuterJoinFilter],
+    )
+    .unwrap();
+    assert!(messages.inner.is_empty());
+}
+
+#[test]
+fn lint_not_in_null() {
+    use prqlc::lint::Lint;
+
+    let messages = prqlc::lint(
+        r#"
+    from employees
+    filter !(dept_id | in [1, null])
+    "#,
+        &[Lint::NotInNull],
+    )
+    .unwrap();
+    assert_snapshot!(messages, @r"
+    [L004] Lint:
+       ╭─[:3:12]
+       │
+     3 │     filter !(dept_id | in [1, null])
+       │            ────────────┬────────────
+       │                        ╰────────────── a negated `in` against an array containing null is never true, so it removes every row
+       │
+       │ Help: remove the null from the array and check for it separately
+    ───╯
+    ");
+
+    // without the negation, or without a null, the check behaves as expected
+    let messages = prqlc::lint(
+        r#"
+    from employees
+    filter (dept_id | in [1, null])
+    filter !(dept_id | in [1, 2])
+    "#,
+        &[Lint::NotInNull],
+    )
+    .unwrap();
+    assert!(messages.inner.is_empty());
+}
+
+#[test]
+fn test_missing_args() {
+    assert_snapshot!(compile(r###"
+    from film
+    select {film_id, lag film_id}
+    "###).unwrap_err(), @r"
+    Error:
+       ╭─[:3:22]
+       │
+     3 │     select {film_id, lag film_id}
+       │                      ─────┬─────
+       │                           ╰─────── Missing argument `column` to function `lag`
+    ───╯
+    ");
+
+    assert_snapshot!(compile(r###"
+    let plus = a b -> a + b
+
+    from x
+    derive {y = plus, z = (plus 1)}
+    "###).unwrap_err(), @r"
+    Error:
+       ╭─[:5:17]
+       │
+     5 │     derive {y = plus, z = (plus 1)}
+       │                 ──┬─
+       │                   ╰─── Missing arguments `a`, `b` to function `plus`
+    ───╯
+    ");
+}
+
+#[test]
+fn test_in_relation() {
+    assert_snapshot!(compile(r###"
+    from employees
+    filter !(id | in (from blocked | select {id}))
+    "###).unwrap_err(), @r"
+    Error:
+       ╭─[:3:19]
+       │
+     3 │     filter !(id | in (from blocked | select {id}))
+       │                   ───────────────┬───────────────
+       │                                  ╰───────────────── `in` with a relation can only be a condition of `filter`, or one of its `&&` operands
+       │
+       │ Help: to keep rows that have no match in it, use `join side:anti`
+    ───╯
+    ");
+
+    assert_snapshot!(compile(r###"
+    from employees
+    filter (id | in (from blocked))
+    "###).unwrap_err(), @r"
+    Error:
+       ╭─[:3:18]
+       │
+     3 │     filter (id | in (from blocked))
+       │                  ────────┬────────
+       │                          ╰────────── `in` requires a relation with exactly one column
+       │
+       │ Help: add a `select` of the column to the relation
+    ───╯
+    ");
+}
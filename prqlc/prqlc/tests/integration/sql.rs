@@ -193,6 +193,189 @@ fn test_stdlib_text_module() {
     );
 }
 
+#[test]
+fn test_stdlib_text_like_escape() {
+    // wildcards in literal arguments match literally
+    let query = r#"
+    from products
+    select {
+      sale = (name | text.starts_with "100%"),
+      code = (name | text.contains "a_b"),
+      wow = (name | text.ends_with "50!"),
+    }
+    "#;
+
+    assert_snapshot!(compile(query).unwrap(), @r"
+    SELECT
+      name LIKE CONCAT('100!%', '%') ESCAPE '!' AS sale,
+      name LIKE CONCAT('%', 'a!_b', '%') ESCAPE '!' AS code,
+      name LIKE CONCAT('%', '50!!') ESCAPE '!' AS wow
+    FROM
+      products
+    ");
+    assert_snapshot!(compile_with_sql_dialect(query, sql::Dialect::Postgres).unwrap(), @r"
+    SELECT
+      name LIKE CONCAT('100\%', '%') AS sale,
+      name LIKE CONCAT('%', 'a\_b', '%') AS code,
+      name LIKE CONCAT('%', '50!') AS wow
+    FROM
+      products
+    ");
+    assert_snapshot!(compile_with_sql_dialect(query, sql::Dialect::MySql).unwrap(), @r"
+    SELECT
+      name LIKE CONCAT('100\\%', '%') AS sale,
+      name LIKE CONCAT('%', 'a\\_b', '%') AS code,
+      name LIKE CONCAT('%', '50!') AS wow
+    FROM
+      products
+    ");
+}
+
+#[test]
+fn test_stdlib_text_case_insensitive() {
+    let query = r#"
+    from employees
+    select {
+      name_starts_with = (name | text.istarts_with "pika"),
+      name_contains = (name | text.icontains "pika"),
+      name_ends_with = (name | text.iends_with "pika"),
+    }
+    "#;
+
+    assert_snapshot!(compile(query).unwrap(), @r"
+    SELECT
+      LOWER(name) LIKE CONCAT(LOWER('pika'), '%') AS name_starts_with,
+      LOWER(name) LIKE CONCAT('%', LOWER('pika'), '%') AS name_contains,
+      LOWER(name) LIKE CONCAT('%', LOWER('pika')) AS name_ends_with
+    FROM
+      employees
+    ");
+    assert_snapshot!(compile_with_sql_dialect(query, sql::Dialect::Postgres).unwrap(), @r"
+    SELECT
+      name ILIKE CONCAT('pika', '%') AS name_starts_with,
+      name ILIKE CONCAT('%', 'pika', '%') AS name_contains,
+      name ILIKE CONCAT('%', 'pika') AS name_ends_with
+    FROM
+      employees
+    ");
+    assert_snapshot!(compile_with_sql_dialect(query, sql::Dialect::SQLite).unwrap(), @r"
+    SELECT
+      LOWER(name) LIKE LOWER('pika') || '%' AS name_starts_with,
+      LOWER(name) LIKE '%' || LOWER('pika') || '%' AS name_contains,
+      LOWER(name) LIKE '%' || LOWER('pika') AS name_ends_with
+    FROM
+      employees
+    ");
+}
+
+#[test]
+fn test_stdlib_array() {
+    let query = r#"
+    from posts
+    derive {names = s"ARRAY_CAT({first_names}, {last_names})"}
+    select {
+      first_tag = (tags | array_get 1),
+      top_tags = (tags | array_slice 1 3),
+      first_name = (names | array_get 1),
+    }
+    "#;
+
+    assert_snapshot!(compile_with_sql_dialect(query, sql::Dialect::Postgres).unwrap(), @r"
+    SELECT
+      tags [1] AS first_tag,
+      tags [1:3] AS top_tags,
+      (ARRAY_CAT(first_names, last_names)) [1] AS first_name
+    FROM
+      posts
+    ");
+    assert_snapshot!(compile_with_sql_dialect(query, sql::Dialect::SQLite).unwrap_err(), @r"
+    Error:
+       ╭─[:5:27]
+       │
+     5 │       first_tag = (tags | array_get 1),
+       │                           ─────┬─────
+       │                                ╰─────── operator std.array_get is not supported for dialect sqlite
+    ───╯
+    ");
+}
+
+#[test]
+fn test_stdlib_date_arithmetic() {
+    let query = r#"
+    from subscriptions
+    derive {
+      days = date.diff "day" started ended,
+      renews = (started | date.add "month" (months + 1)),
+    }
+    select {days, renews}
+    "#;
+
+    assert_snapshot!(compile_with_sql_dialect(query, sql::Dialect::Postgres).unwrap(), @r"
+    SELECT
+      CAST(ended AS DATE) - CAST(started AS DATE) AS days,
+      started + (months + 1) * INTERVAL '1 month' AS renews
+    FROM
+      subscriptions
+    ");
+    assert_snapshot!(compile_with_sql_dialect(query, sql::Dialect::MySql).unwrap(), @r"
+    SELECT
+      TIMESTAMPDIFF(DAY, started, ended) AS days,
+      DATE_ADD(started, INTERVAL months + 1 MONTH) AS renews
+    FROM
+      subscriptions
+    ");
+    assert_snapshot!(compile_with_sql_dialect(query, sql::Dialect::MsSql).unwrap(), @r"
+    SELECT
+      DATEDIFF(DAY, started, ended) AS days,
+      DATEADD(MONTH, months + 1, started) AS renews
+    FROM
+      subscriptions
+    ");
+    assert_snapshot!(compile_with_sql_dialect(query, sql::Dialect::DuckDb).unwrap(), @r"
+    SELECT
+      date_diff('day', started, ended) AS days,
+      started + (months + 1) * INTERVAL '1 month' AS renews
+    FROM
+      subscriptions
+    ");
+
+    // MySQL supports all units
+    assert_snapshot!(compile_with_sql_dialect(r#"
+    from subscriptions
+    derive {months = date.diff "month" started ended}
+    "#, sql::Dialect::MySql).unwrap(), @r"
+    SELECT
+      *,
+      TIMESTAMPDIFF(MONTH, started, ended) AS months
+    FROM
+      subscriptions
+    ");
+    assert_snapshot!(compile_with_sql_dialect(r#"
+    from subscriptions
+    derive {months = date.diff "month" started ended}
+    "#, sql::Dialect::Postgres).unwrap_err(), @r#"
+    Error:
+       ╭─[:3:32]
+       │
+     3 │     derive {months = date.diff "month" started ended}
+       │                                ───┬───
+       │                                   ╰───── `std.date.diff` only supports "day" as unit for this dialect
+    ───╯
+    "#);
+    assert_snapshot!(compile(r#"
+    from subscriptions
+    derive {fortnights = date.diff "fortnight" started ended}
+    "#).unwrap_err(), @r#"
+    Error:
+       ╭─[:3:36]
+       │
+     3 │     derive {fortnights = date.diff "fortnight" started ended}
+       │                                    ─────┬─────
+       │                                         ╰─────── `std.date.diff` only supports one of "year", "month", "week", "day", "hour", "minute", "second" as unit
+    ───╯
+    "#);
+}
+
 #[rstest]
 #[case::generic(sql::Dialect::Generic, "LIKE CONCAT('%', 'pika', '%')")]
 #[case::sqlite(sql::Dialect::SQLite, "LIKE '%' || 'pika' || '%'")] // `CONCAT` is not supported in SQLite
@@ -217,6 +400,35 @@ FROM
     )
 }
 
+#[rstest]
+#[case::postgres(sql::Dialect::Postgres, "(a * 1.0 / b)")]
+#[case::sqlite(sql::Dialect::SQLite, "(a * 1.0 / b)")]
+#[case::mssql(sql::Dialect::MsSql, "(a * 1.0 / b)")]
+#[case::bigquery(sql::Dialect::BigQuery, "(a * 1.0 / b)")]
+// These return a float when dividing integers, and the generic dialect
+// leaves `/` unchanged
+#[case::duckdb(sql::Dialect::DuckDb, "(a / b)")]
+#[case::mysql(sql::Dialect::MySql, "(a / b)")]
+#[case::generic(sql::Dialect::Generic, "a / b")]
+fn float_division(#[case] dialect: sql::Dialect, #[case] expected_division: &'static str) {
+    let query = r#"
+  from t
+  select {ratio = a / b}
+  "#;
+    let expected = format!(
+        r#"
+SELECT
+  {expected_division} AS ratio
+FROM
+  t
+"#
+    );
+    assert_eq!(
+        compile_with_sql_dialect(query, dialect).unwrap(),
+        expected.trim_start()
+    )
+}
+
 #[rstest]
 #[case::clickhouse(
     sql::Dialect::ClickHouse,
@@ -249,6 +461,33 @@ FROM
     )
 }
 
+#[rstest]
+#[case::postgres(
+    sql::Dialect::Postgres,
+    r#"TO_CHAR(created, 'YYYY-MM-DD HH24:MI "at" FMMonth')"#
+)]
+#[case::mysql(sql::Dialect::MySql, "DATE_FORMAT(created, '%Y-%m-%d %H:%i at %M')")]
+#[case::duckdb(sql::Dialect::DuckDb, "strftime(created, '%Y-%m-%d %H:%M at %B')")]
+fn date_to_char_operator(#[case] dialect: sql::Dialect, #[case] expected_to_char: &'static str) {
+    let query = r#"
+    from orders
+    select {
+      created = (created | date.to_char 'YYYY-MM-DD HH24:MI "at" Month')
+    }"#;
+    let expected = format!(
+        r#"
+SELECT
+  {expected_to_char} AS created
+FROM
+  orders
+"#
+    );
+    assert_eq!(
+        compile_with_sql_dialect(query, dialect).unwrap(),
+        expected.trim_start()
+    )
+}
+
 #[test]
 fn json_of_test() {
     let pl = prqlc::prql_to_pl("from employees | take 10").unwrap();
@@ -259,6 +498,103 @@ fn json_of_test() {
     assert_eq!(json.chars().nth(json.len() - 1).unwrap(), '}');
 }
 
+/// The snapshot is named after the schema version. If it fails because the
+/// shape of PL or RQ changed, bump `json::SCHEMA_VERSION` (which names a new
+/// snapshot) and remove the old one, rather than updating it in place.
+#[test]
+fn json_schema() {
+    let schema = serde_json::json!({
+        "pl": schemars::schema_for!(prqlc::pr::ModuleDef),
+        "rq": schemars::schema_for!(prqlc::ir::rq::RelationalQuery),
+    });
+    assert_snapshot!(
+        format!("json_schema_v{}", prqlc::json::SCHEMA_VERSION),
+        serde_json::to_string_pretty(&schema).unwrap()
+    );
+}
+
+#[test]
+fn json_versioned_round_trip() {
+    use prqlc::json::SCHEMA_VERSION;
+
+    let pl = prqlc::prql_to_pl("from employees | filter salary > 100 | take 10").unwrap();
+    let json = prqlc::json::from_pl_versioned(&pl).unwrap();
+    assert!(json.starts_with(&format!(r#"{{"schema_version":{SCHEMA_VERSION},"ast":{{"#)));
+    assert_eq!(prqlc::json::to_pl_versioned(&json).unwrap(), pl);
+
+    let rq = prqlc::pl_to_rq(pl).unwrap();
+    let json = prqlc::json::from_rq_versioned(&rq).unwrap();
+    assert_eq!(prqlc::json::to_rq_versioned(&json).unwrap(), rq);
+
+    let next = SCHEMA_VERSION + 1;
+    let future = json.replacen(
+        &format!(r#""schema_version":{SCHEMA_VERSION}"#),
+        &format!(r#""schema_version":{next}"#),
+        1,
+    );
+    let err = prqlc::json::to_rq_versioned(&future).unwrap_err();
+    assert_eq!(
+        err.inner[0].reason,
+        format!("unsupported JSON schema version {next}, expected {SCHEMA_VERSION}")
+    );
+
+    // unversioned JSON is rejected
+    let unversioned = prqlc::json::from_rq(&rq).unwrap();
+    assert!(prqlc::json::to_rq_versioned(&unversioned).is_err());
+}
+
+#[test]
+fn binary_round_trip() {
+    let prql = r#"
+    from e = employees
+    join side:left d = departments (==dept_id)
+    derive {gross = e.salary + e.benefits}
+    sort {-gross}
+    take 10
+    "#;
+
+    let pl = prqlc::prql_to_pl(prql).unwrap();
+    let bytes = prqlc::binary::from_pl(&pl).unwrap();
+    let pl_decoded = prqlc::binary::to_pl(&bytes).unwrap();
+    assert_eq!(pl_decoded, pl);
+    assert_eq!(prqlc::binary::from_pl(&pl_decoded).unwrap(), bytes);
+
+    // much more compact than JSON
+    assert!(bytes.len() < prqlc::json::from_pl(&pl).unwrap().len());
+
+    let rq = prqlc::pl_to_rq(pl).unwrap();
+    let bytes = prqlc::binary::from_rq(&rq).unwrap();
+    let rq_decoded = prqlc::binary::to_rq(&bytes).unwrap();
+    assert_eq!(rq_decoded, rq);
+    assert_eq!(prqlc::binary::from_rq(&rq_decoded).unwrap(), bytes);
+
+    assert!(prqlc::binary::to_rq(&bytes[..bytes.len() / 2]).is_err());
+}
+
+#[test]
+fn test_query_metadata() {
+    let prql = r#"
+    prql target:sql.postgres
+
+    @name "monthly_report"
+    @owner "team-data"
+    from invoices
+    "#;
+
+    let rq = prqlc::prql_to_pl(prql).and_then(prqlc::pl_to_rq).unwrap();
+    assert_eq!(rq.def.metadata["name"], "monthly_report");
+    assert_eq!(rq.def.metadata["owner"], "team-data");
+    assert_eq!(rq.def.metadata.len(), 2);
+
+    // the metadata doesn't affect the SQL
+    assert_snapshot!(compile(prql).unwrap(), @r"
+    SELECT
+      *
+    FROM
+      invoices
+    ");
+}
+
 #[test]
 fn test_precedence_division() {
     assert_snapshot!((compile(r###"
@@ -1009,6 +1345,37 @@ fn test_quoting_05() {
     "#);
 }
 
+#[test]
+fn test_quoting_06() {
+    // `usage` is reserved in MySQL, but not in Postgres
+    let query = r#"
+        from scores
+        select {usage, key}
+    "#;
+
+    assert_snapshot!(compile_with_sql_dialect(query, sql::Dialect::MySql).unwrap(), @r"
+    SELECT
+      `usage`,
+      `key`
+    FROM
+      scores
+    ");
+    assert_snapshot!(compile_with_sql_dialect(query, sql::Dialect::Postgres).unwrap(), @r"
+    SELECT
+      usage,
+      key
+    FROM
+      scores
+    ");
+    assert_snapshot!(compile_with_sql_dialect(query, sql::Dialect::SQLite).unwrap(), @r#"
+    SELECT
+      usage,
+      "key"
+    FROM
+      scores
+    "#);
+}
+
 #[test]
 fn test_sorts_01() {
     assert_snapshot!((compile(r###"
@@ -1118,6 +1485,105 @@ fn test_sorts_03() {
     ");
 }
 
+#[test]
+fn test_sorts_04() {
+    // keywords are equivalent to the prefixes, and can be mixed with them
+    assert_snapshot!((compile(r#"
+    from employees
+    sort {salary desc, name asc}
+    "#).unwrap()), @r"
+    SELECT
+      *
+    FROM
+      employees
+    ORDER BY
+      salary DESC,
+      name
+    ");
+
+    assert_snapshot!((compile(r#"
+    from employees
+    sort {-tenure, salary desc, +age, name}
+    "#).unwrap()), @r"
+    SELECT
+      *
+    FROM
+      employees
+    ORDER BY
+      tenure DESC,
+      salary DESC,
+      age,
+      name
+    ");
+}
+
+#[test]
+fn test_sorts_05() {
+    // a sort after the aggregate of a group orders the groups themselves
+    assert_snapshot!((compile(r#"
+    from employees
+    group {department} (
+      aggregate {total = sum salary}
+      sort {-total}
+    )
+    take 3
+    "#).unwrap()), @r"
+    SELECT
+      department,
+      COALESCE(SUM(salary), 0) AS total
+    FROM
+      employees
+    GROUP BY
+      department
+    ORDER BY
+      total DESC
+    LIMIT
+      3
+    ");
+
+    // a sort before it only orders rows within each group, which doesn't
+    // affect the aggregate
+    assert_snapshot!((compile(r#"
+    from employees
+    group {department} (
+      sort {-salary}
+      aggregate {total = sum salary}
+    )
+    "#).unwrap()), @r"
+    SELECT
+      department,
+      COALESCE(SUM(salary), 0) AS total
+    FROM
+      employees
+    GROUP BY
+      department
+    ");
+}
+
+#[test]
+fn test_sorts_06() {
+    // a derived column can be grouped by and sorted on: GROUP BY repeats its
+    // expression, while ORDER BY refers to it by its alias
+    assert_snapshot!((compile(r#"
+    from employees
+    derive {decade = (hired_year / 10 | math.floor) * 10}
+    group {decade} (
+      aggregate {hires = count this}
+    )
+    sort {-decade}
+    "#).unwrap()), @r"
+    SELECT
+      FLOOR(hired_year / 10) * 10 AS decade,
+      COUNT(*) AS hires
+    FROM
+      employees
+    GROUP BY
+      FLOOR(hired_year / 10) * 10
+    ORDER BY
+      decade DESC
+    ");
+}
+
 #[test]
 fn test_numbers() {
     let query = r###"
@@ -1273,22 +1739,65 @@ fn test_in_values_err_01() {
 }
 
 #[test]
-fn test_interval() {
-    let query = r###"
-    from projects
-    derive first_check_in = start + 10days
-    "###;
-
-    assert_snapshot!((compile(query).unwrap()), @r#"
+fn test_overlaps() {
+    assert_snapshot!((compile_with_sql_dialect(r###"
+    from bookings
+    filter (period | overlaps @2024-01-01..@2024-02-01)
+    "###, sql::Dialect::Postgres).unwrap()), @r"
     SELECT
-      *,
-      "start" + INTERVAL 10 DAY AS first_check_in
+      *
     FROM
-      projects
-    "#);
-
-    let query = r###"
-    prql target:sql.postgres
+      bookings
+    WHERE
+      NOT ISEMPTY(
+        period * tstzrange(DATE '2024-01-01', DATE '2024-02-01')
+      )
+    ");
+
+    // a pair of values is compared as a period
+    assert_snapshot!((compile(r###"
+    from bookings
+    filter ({check_in, check_out} | overlaps @2024-01-01..@2024-02-01)
+    "###).unwrap()), @r"
+    SELECT
+      *
+    FROM
+      bookings
+    WHERE
+      (check_in, check_out) OVERLAPS (DATE '2024-01-01', DATE '2024-02-01')
+    ");
+
+    assert_snapshot!((compile(r###"
+    from bookings
+    filter (period | overlaps @2024-01-01..)
+    "###).unwrap_err()), @r"
+    Error:
+       ╭─[:3:31]
+       │
+     3 │     filter (period | overlaps @2024-01-01..)
+       │                               ──────┬──────
+       │                                     ╰──────── `overlaps` requires a range with both bounds
+    ───╯
+    ");
+}
+
+#[test]
+fn test_interval() {
+    let query = r###"
+    from projects
+    derive first_check_in = start + 10days
+    "###;
+
+    assert_snapshot!((compile(query).unwrap()), @r#"
+    SELECT
+      *,
+      "start" + INTERVAL 10 DAY AS first_check_in
+    FROM
+      projects
+    "#);
+
+    let query = r###"
+    prql target:sql.postgres
 
     from projects
     derive first_check_in = start + 10days
@@ -1679,6 +2188,228 @@ fn test_window_functions_13() {
     ");
 }
 
+#[test]
+fn test_window_functions_14() {
+    // lag and lead with a default for rows without one at the offset
+    assert_snapshot!((compile(r###"
+    from prices
+    group ticker (
+      sort date
+      derive {
+        prev = lag 1 price default:0,
+        next = lead 2 price,
+        next_or_same = (price | lead 1 default:price),
+      }
+    )
+    "###).unwrap()), @r"
+    SELECT
+      *,
+      LAG(price, 1, 0) OVER (
+        PARTITION BY ticker
+        ORDER BY
+          date
+      ) AS prev,
+      LEAD(price, 2) OVER (
+        PARTITION BY ticker
+        ORDER BY
+          date
+      ) AS next,
+      LEAD(price, 1, price) OVER (
+        PARTITION BY ticker
+        ORDER BY
+          date
+      ) AS next_or_same
+    FROM
+      prices
+    ");
+}
+
+#[test]
+fn test_aggregate_first_last() {
+    // first & last are window functions, so they are computed over the
+    // sorted group & then used as grouping keys
+    assert_snapshot!((compile(r###"
+    from events
+    group {user_id} (
+      sort ts
+      aggregate {latest = last status, n = count this}
+    )
+    "###).unwrap()), @r"
+    WITH table_0 AS (
+      SELECT
+        user_id,
+        LAST_VALUE(status) OVER (
+          PARTITION BY user_id
+          ORDER BY
+            ts ROWS BETWEEN UNBOUNDED PRECEDING AND UNBOUNDED FOLLOWING
+        ) AS latest,
+        status,
+        ts
+      FROM
+        events
+    )
+    SELECT
+      user_id,
+      latest,
+      COUNT(*) AS n
+    FROM
+      table_0
+    GROUP BY
+      user_id,
+      latest
+    ");
+
+    assert_snapshot!((compile(r###"
+    from events
+    group {user_id} (
+      sort ts
+      aggregate {earliest = first status}
+    )
+    "###).unwrap()), @r"
+    WITH table_0 AS (
+      SELECT
+        user_id,
+        FIRST_VALUE(status) OVER (
+          PARTITION BY user_id
+          ORDER BY
+            ts ROWS BETWEEN UNBOUNDED PRECEDING AND UNBOUNDED FOLLOWING
+        ) AS earliest,
+        status,
+        ts
+      FROM
+        events
+    )
+    SELECT
+      user_id,
+      earliest
+    FROM
+      table_0
+    GROUP BY
+      user_id,
+      earliest
+    ");
+}
+
+#[test]
+fn test_aggregate_unused() {
+    // the aggregation is kept, as it leaves a single row
+    assert_snapshot!((compile(r###"
+    from employees
+    aggregate {total = sum salary}
+    derive {x = 5}
+    select {x}
+    "###).unwrap()), @r"
+    WITH table_0 AS (
+      SELECT
+        COALESCE(SUM(salary), 0)
+      FROM
+        employees
+    )
+    SELECT
+      5 AS x
+    FROM
+      table_0
+    ");
+}
+
+#[test]
+fn test_aggregate_statistical() {
+    assert_snapshot!((compile(r###"
+    from measurements
+    group {site} (
+      aggregate {
+        r = corr y x,
+        cov = covar_samp y x,
+        slope = regr_slope y x,
+      }
+    )
+    "###).unwrap()), @r"
+    SELECT
+      site,
+      CORR(y, x) AS r,
+      COVAR_SAMP(y, x) AS cov,
+      REGR_SLOPE(y, x) AS slope
+    FROM
+      measurements
+    GROUP BY
+      site
+    ");
+
+    assert_snapshot!((compile(r###"
+    from measurements
+    derive {r2 = regr_r2 y x}
+    "###).unwrap()), @r"
+    SELECT
+      *,
+      REGR_R2(y, x) OVER () AS r2
+    FROM
+      measurements
+    ");
+
+    assert_snapshot!(compile_with_sql_dialect(r###"
+    from measurements
+    aggregate {cov = covar_pop y x}
+    "###, sql::Dialect::ClickHouse).unwrap(), @r"
+    SELECT
+      covarPop(y, x) AS cov
+    FROM
+      measurements
+    ");
+
+    assert_snapshot!(compile_with_sql_dialect(r###"
+    from measurements
+    aggregate {r = corr y x}
+    "###, sql::Dialect::SQLite).unwrap_err(), @r"
+    Error:
+       ╭─[:3:20]
+       │
+     3 │     aggregate {r = corr y x}
+       │                    ────┬───
+       │                        ╰───── operator std.corr is not supported for dialect sqlite
+    ───╯
+    ");
+}
+
+#[test]
+fn test_count_distinct_columns() {
+    let query = r###"
+    from orders
+    group {customer_id} (
+      aggregate {n_products = count_distinct {product_id, variant_id}}
+    )
+    "###;
+
+    assert_snapshot!(compile_with_sql_dialect(query, sql::Dialect::Postgres).unwrap(), @r"
+    SELECT
+      customer_id,
+      COUNT(DISTINCT (product_id, variant_id)) AS n_products
+    FROM
+      orders
+    GROUP BY
+      customer_id
+    ");
+
+    assert_snapshot!(compile_with_sql_dialect(query, sql::Dialect::MySql).unwrap(), @r"
+    SELECT
+      customer_id,
+      COUNT(DISTINCT product_id, variant_id) AS n_products
+    FROM
+      orders
+    GROUP BY
+      customer_id
+    ");
+
+    assert_snapshot!(compile_with_sql_dialect(query, sql::Dialect::SQLite).unwrap_err(), @r"
+    Error:
+       ╭─[:4:31]
+       │
+     4 │       aggregate {n_products = count_distinct {product_id, variant_id}}
+       │                               ───────────────────┬───────────────────
+       │                                                  ╰───────────────────── counting distinct values of multiple columns is not supported for dialect sqlite
+    ───╯
+    ");
+}
+
 #[test]
 fn test_window_single_item_range() {
     assert_snapshot!(compile(r###"
@@ -1813,7 +2544,7 @@ fn test_nulls_02() {
     "###).unwrap()), @r"
     SELECT
       *,
-      COALESCE(amount + 2, 3 * 5) AS amount
+      COALESCE(amount + 2, 15) AS amount
     FROM
       employees
     ");
@@ -1853,6 +2584,201 @@ fn test_nulls_04() {
     ");
 }
 
+#[test]
+fn test_nulls_05() {
+    // nested coalesce is flattened into a single COALESCE
+    assert_snapshot!((compile(r###"
+    from employees
+    select {
+      a = nickname ?? first_name ?? "unknown",
+      b = coalesce nickname (coalesce first_name last_name),
+      c = (coalesce nickname first_name) + 1,
+    }
+    "###).unwrap()), @r"
+    SELECT
+      COALESCE(nickname, first_name, 'unknown') AS a,
+      COALESCE(nickname, first_name, last_name) AS b,
+      COALESCE(nickname, first_name) + 1 AS c
+    FROM
+      employees
+    ");
+}
+
+#[test]
+fn test_nulls_06() {
+    // greatest & least use the dialect's semantics by default; `null_safe`
+    // makes each operand fall back to the other, so NULLs are skipped
+    let query = r###"
+    from employees
+    derive {
+      a = greatest salary bonus,
+      b = least salary bonus,
+      c = greatest salary bonus null_safe:true,
+      d = least salary bonus null_safe:true,
+    }
+    "###;
+
+    assert_snapshot!((compile(query).unwrap()), @r"
+    SELECT
+      *,
+      GREATEST(salary, bonus) AS a,
+      LEAST(salary, bonus) AS b,
+      GREATEST(COALESCE(salary, bonus), COALESCE(bonus, salary)) AS c,
+      LEAST(COALESCE(salary, bonus), COALESCE(bonus, salary)) AS d
+    FROM
+      employees
+    ");
+
+    assert_snapshot!((compile_with_sql_dialect(query, sql::Dialect::SQLite).unwrap()), @r"
+    SELECT
+      *,
+      MAX(salary, bonus) AS a,
+      MIN(salary, bonus) AS b,
+      MAX(COALESCE(salary, bonus), COALESCE(bonus, salary)) AS c,
+      MIN(COALESCE(salary, bonus), COALESCE(bonus, salary)) AS d
+    FROM
+      employees
+    ");
+}
+
+#[test]
+fn test_nulls_07() {
+    // null-safe comparisons treat two nulls as equal
+    let query = r###"
+    from employees
+    filter (is_distinct_from manager_id null) || (is_distinct_from (salary + 1) bonus)
+    select {
+      a = is_distinct_from salary bonus,
+      b = is_not_distinct_from salary bonus,
+    }
+    "###;
+
+    assert_snapshot!((compile(query).unwrap()), @r"
+    SELECT
+      salary IS DISTINCT FROM bonus AS a,
+      salary IS NOT DISTINCT FROM bonus AS b
+    FROM
+      employees
+    WHERE
+      manager_id IS DISTINCT FROM NULL
+      OR salary + 1 IS DISTINCT FROM bonus
+    ");
+
+    assert_snapshot!((compile_with_sql_dialect(query, sql::Dialect::MySql).unwrap()), @r"
+    SELECT
+      NOT salary <=> bonus AS a,
+      salary <=> bonus AS b
+    FROM
+      employees
+    WHERE
+      NOT manager_id <=> NULL
+      OR NOT salary + 1 <=> bonus
+    ");
+
+    assert_snapshot!((compile_with_sql_dialect(query, sql::Dialect::SQLite).unwrap()), @r"
+    SELECT
+      salary IS NOT bonus AS a,
+      salary IS bonus AS b
+    FROM
+      employees
+    WHERE
+      manager_id IS NOT NULL
+      OR salary + 1 IS NOT bonus
+    ");
+
+    assert_snapshot!((compile_with_sql_dialect(query, sql::Dialect::ClickHouse).unwrap_err()), @r"
+    Error:
+       ╭─[:5:11]
+       │
+     5 │       a = is_distinct_from salary bonus,
+       │           ──────────────┬──────────────
+       │                         ╰──────────────── operator std.is_distinct_from is not supported for dialect clickhouse
+    ───╯
+    ");
+}
+
+#[test]
+fn test_rename() {
+    // known columns are renamed in place
+    assert_snapshot!((compile(r###"
+    from employees
+    select {id, first_name, last_name}
+    rename {given_name = first_name}
+    filter given_name != null
+    "###).unwrap()), @r"
+    WITH table_0 AS (
+      SELECT
+        id,
+        first_name AS given_name,
+        last_name
+      FROM
+        employees
+    )
+    SELECT
+      id,
+      given_name,
+      last_name
+    FROM
+      table_0
+    WHERE
+      given_name IS NOT NULL
+    ");
+
+    // unknown columns are renamed in the star
+    assert_snapshot!((compile_with_sql_dialect(r###"
+    from employees
+    rename {given_name = first_name}
+    derive initial = (given_name | text.extract 1 1)
+    "###, sql::Dialect::DuckDb).unwrap()), @r"
+    SELECT
+      * RENAME (first_name AS given_name),
+      SUBSTRING(first_name, 1, 1) AS initial
+    FROM
+      employees
+    ");
+
+    // ... or excluded from it
+    assert_snapshot!((compile_with_sql_dialect(r###"
+    from employees
+    rename {given_name = first_name}
+    "###, sql::Dialect::BigQuery).unwrap()), @r"
+    SELECT
+      * EXCEPT (first_name),
+      first_name AS given_name
+    FROM
+      employees
+    ");
+
+    // ... which some dialects cannot do
+    assert_snapshot!((compile(r###"
+    from employees
+    rename {given_name = first_name}
+    "###).unwrap_err()), @r"
+    Error:
+       ╭─[:3:26]
+       │
+     3 │     rename {given_name = first_name}
+       │                          ─────┬────
+       │                               ╰────── cannot rename `first_name`, as the other columns of the relation are not known
+       │
+       │ Help: this dialect cannot exclude columns from `*`, so select the columns first
+    ───╯
+    ");
+
+    assert_snapshot!((compile(r###"
+    from employees
+    rename {first_name + last_name}
+    "###).unwrap_err()), @r"
+    Error:
+       ╭─[:3:13]
+       │
+     3 │     rename {first_name + last_name}
+       │             ───────────┬──────────
+       │                        ╰──────────── `rename` expects columns with new names, such as `new_name = old_name`
+    ───╯
+    ");
+}
+
 #[test]
 fn test_take_01() {
     assert_snapshot!((compile(r###"
@@ -2016,7 +2942,138 @@ fn test_take_10() {
        │
      3 │     take (-1)
        │     ────┬────
-       │         ╰────── take expected a positive int range, but found ..-1
+       │         ╰────── taking rows from the bottom requires the relation to be sorted
+       │
+       │ Help: add a `sort` before the `take`
+    ───╯
+    ");
+}
+
+#[test]
+fn test_take_11() {
+    // a negative count takes rows from the bottom of the current sort
+    assert_snapshot!((compile(r###"
+    from employees
+    sort {hire_date, -id}
+    take (-3)
+    "###).unwrap()), @r"
+    WITH table_0 AS (
+      SELECT
+        *
+      FROM
+        employees
+      ORDER BY
+        hire_date DESC,
+        id
+      LIMIT
+        3
+    )
+    SELECT
+      *
+    FROM
+      table_0
+    ORDER BY
+      hire_date,
+      id DESC
+    ");
+}
+
+#[test]
+fn test_take_12() {
+    // `take 0` returns no rows, and later transforms still apply
+    assert_snapshot!(compile(r###"
+    from employees
+    take 0
+    filter salary > 0
+    select {name, salary}
+    "###).unwrap(), @r"
+    WITH table_0 AS (
+      SELECT
+        name,
+        salary
+      FROM
+        employees
+      LIMIT
+        0
+    )
+    SELECT
+      name,
+      salary
+    FROM
+      table_0
+    WHERE
+      salary > 0
+    ");
+
+    assert_snapshot!(compile(r###"
+    from employees
+    take 3..0
+    "###).unwrap_err(), @r"
+    Error:
+       ╭─[:3:5]
+       │
+     3 │     take 3..0
+       │     ────┬────
+       │         ╰────── take expected a positive int range, but found 3..0
+    ───╯
+    ");
+}
+
+#[test]
+fn test_take_13() {
+    // `take first` and `take last` take rows of the current sort
+    assert_snapshot!(compile(r###"
+    from employees
+    sort hire_date
+    take first 5
+    "###).unwrap(), @r"
+    SELECT
+      *
+    FROM
+      employees
+    ORDER BY
+      hire_date
+    LIMIT
+      5
+    ");
+
+    assert_snapshot!(compile(r###"
+    from employees
+    sort {hire_date, -id}
+    take last 3
+    "###).unwrap(), @r"
+    WITH table_0 AS (
+      SELECT
+        *
+      FROM
+        employees
+      ORDER BY
+        hire_date DESC,
+        id
+      LIMIT
+        3
+    )
+    SELECT
+      *
+    FROM
+      table_0
+    ORDER BY
+      hire_date,
+      id DESC
+    ");
+
+    assert_snapshot!(compile(r###"
+    from employees
+    take first 5
+    "###).unwrap_err(), @r"
+    Error:
+       ╭─[:3:5]
+       │
+     3 │     take first 5
+       │     ──────┬─────
+       │           ╰─────── `take first` and `take last` require the relation to be sorted
+       │
+       │ Help: add a `sort` before the `take`
     ───╯
     ");
 }
@@ -2382,34 +3439,86 @@ fn test_group_take_n_01() {
     ");
 }
 
-#[test]
-fn test_group_take_n_02() {
+#[test]
+fn test_group_take_n_02() {
+    assert_snapshot!((compile(r###"
+    prql target:sql.postgres
+
+    from employees
+    group department (
+      sort age
+      take 2..
+    )
+    "###).unwrap()),  @r"
+    WITH table_0 AS (
+      SELECT
+        *,
+        ROW_NUMBER() OVER (
+          PARTITION BY department
+          ORDER BY
+            age
+        ) AS _expr_0
+      FROM
+        employees
+    )
+    SELECT
+      *
+    FROM
+      table_0
+    WHERE
+      _expr_0 >= 2
+    ");
+}
+
+#[test]
+fn test_group_take_n_03() {
+    // BigQuery filters by the row number with QUALIFY, so no CTE is needed
+    assert_snapshot!((compile(r###"
+    prql target:sql.bigquery
+
+    from employees
+    group department (
+      sort {-`start date`}
+      take 2
+    )
+    "###).unwrap()), @r"
+    SELECT
+      *
+    FROM
+      employees QUALIFY ROW_NUMBER() OVER (
+        PARTITION BY department
+        ORDER BY
+          `start date` DESC
+      ) <= 2
+    ");
+
+    // ...unless the query continues after the filter
     assert_snapshot!((compile(r###"
-    prql target:sql.postgres
+    prql target:sql.bigquery
 
     from employees
     group department (
       sort age
-      take 2..
+      take 2..3
     )
-    "###).unwrap()),  @r"
+    filter salary > 1000
+    "###).unwrap()), @r"
     WITH table_0 AS (
       SELECT
-        *,
-        ROW_NUMBER() OVER (
+        *
+      FROM
+        employees QUALIFY ROW_NUMBER() OVER (
           PARTITION BY department
           ORDER BY
             age
-        ) AS _expr_0
-      FROM
-        employees
+        ) BETWEEN 2 AND 3
     )
     SELECT
       *
     FROM
       table_0
     WHERE
-      _expr_0 >= 2
+      salary > 1000
     ");
 }
 
@@ -2460,7 +3569,7 @@ fn test_join_side_literal_err() {
        │
      5 │     join y (==id) side:my_side
        │                        ───┬───
-       │                           ╰───── `side` expected inner, left, right or full, but found 42
+       │                           ╰───── `side` expected inner, left, right, full, semi or anti, but found 42
     ───╯
     ");
 }
@@ -2499,11 +3608,167 @@ fn test_join_side_literal_via_func_err() {
        │
      3 │         join side:_param.s m (c == that.k) tbl
        │                         ─┬
-       │                          ╰── `side` expected inner, left, right or full, but found "four"
+       │                          ╰── `side` expected inner, left, right, full, semi or anti, but found "four"
     ───╯
     "#);
 }
 
+#[test]
+fn test_join_semi_anti() {
+    // semi and anti joins compile to correlated EXISTS subqueries
+    assert_snapshot!((compile(r###"
+    from c = customers
+    join side:semi o = orders (c.id == o.customer_id && o.total > 100)
+    select {c.name}
+    "###).unwrap()), @r"
+    SELECT
+      c.name
+    FROM
+      customers AS c
+    WHERE
+      EXISTS (
+        SELECT
+          1
+        FROM
+          orders AS o
+        WHERE
+          c.id = o.customer_id
+          AND o.total > 100
+      )
+    ");
+
+    assert_snapshot!((compile(r###"
+    from c = customers
+    join side:anti o = orders (c.id == o.customer_id)
+    filter (c.age > 18 || c.vip)
+    "###).unwrap()), @r"
+    SELECT
+      c.*
+    FROM
+      customers AS c
+    WHERE
+      (
+        c.age > 18
+        OR c.vip
+      )
+      AND NOT EXISTS (
+        SELECT
+          1
+        FROM
+          orders AS o
+        WHERE
+          c.id = o.customer_id
+      )
+    ");
+
+    // the columns of the right relation are not available afterwards
+    assert_snapshot!((compile(r###"
+    from c = customers
+    join side:semi o = orders (c.id == o.customer_id)
+    select {o.total}
+    "###).unwrap_err()), @r"
+    Error:
+       ╭─[:4:13]
+       │
+     4 │     select {o.total}
+       │             ───┬───
+       │                ╰───── Unknown name `o.total`
+    ───╯
+    ");
+}
+
+#[test]
+fn test_in_relation() {
+    assert_snapshot!((compile(r###"
+    from e = employees
+    filter (e.department_id | in (from d = departments | filter d.active | select {d.id}))
+    select {e.name}
+    "###).unwrap()), @r"
+    WITH table_0 AS (
+      SELECT
+        id
+      FROM
+        departments AS d
+      WHERE
+        active
+    )
+    SELECT
+      e.name
+    FROM
+      employees AS e
+    WHERE
+      e.department_id IN (
+        SELECT
+          table_0.id
+        FROM
+          table_0
+      )
+    ");
+
+    // other conditions are kept in the filter
+    assert_snapshot!((compile(r###"
+    from employees
+    filter (age > 25 && (id | in (from managers | select {employee_id})))
+    "###).unwrap()), @r"
+    WITH table_0 AS (
+      SELECT
+        employee_id
+      FROM
+        managers
+    )
+    SELECT
+      employees.*
+    FROM
+      employees
+    WHERE
+      employees.age > 25
+      AND employees.id IN (
+        SELECT
+          table_0.employee_id
+        FROM
+          table_0
+      )
+    ");
+}
+
+#[test]
+fn test_from_pipeline() {
+    // a pipeline that fits into the outer query is merged into it
+    assert_snapshot!(compile(r###"
+    from (from raw | filter valid)
+    aggregate {n = count this}
+    "###).unwrap(), @r"
+    SELECT
+      COUNT(*) AS n
+    FROM
+      raw
+    WHERE
+      valid
+    ");
+
+    // otherwise it becomes a CTE with a generated name
+    assert_snapshot!(compile(r###"
+    from (from raw | take 10)
+    filter valid
+    aggregate {n = count this}
+    "###).unwrap(), @r"
+    WITH table_0 AS (
+      SELECT
+        valid
+      FROM
+        raw
+      LIMIT
+        10
+    )
+    SELECT
+      COUNT(*) AS n
+    FROM
+      table_0
+    WHERE
+      valid
+    ");
+}
+
 #[test]
 fn test_from_json() {
     // Test that the SQL generated from the JSON of the PRQL is the same as the raw PRQL
@@ -2782,6 +4047,82 @@ fn test_table_definition_with_expr_call() {
     );
 }
 
+#[test]
+fn test_table_definition_forward_reference() {
+    // a relation can read from one that's declared further down
+    assert_snapshot!(compile(r###"
+    let top_customers = (from customer_totals | sort {-total} | take 10)
+    let customer_totals = (
+      from invoices
+      group customer_id (aggregate {total = sum amount})
+    )
+    from top_customers
+    "###).unwrap(), @r"
+    WITH customer_totals AS (
+      SELECT
+        customer_id,
+        COALESCE(SUM(amount), 0) AS total
+      FROM
+        invoices
+      GROUP BY
+        customer_id
+    ),
+    top_customers AS (
+      SELECT
+        customer_id,
+        total
+      FROM
+        customer_totals
+      ORDER BY
+        total DESC
+      LIMIT
+        10
+    )
+    SELECT
+      customer_id,
+      total
+    FROM
+      top_customers
+    ORDER BY
+      total DESC
+    ");
+
+    // reading from a relation of the same name is reading from the database
+    assert_snapshot!(compile(r###"
+    let invoices = (from invoices | filter paid)
+    from invoices
+    "###).unwrap(), @r"
+    WITH table_0 AS (
+      SELECT
+        *
+      FROM
+        invoices
+      WHERE
+        paid
+    )
+    SELECT
+      *
+    FROM
+      table_0 AS invoices
+    ");
+
+    assert_snapshot!(compile(r###"
+    let a = (from c)
+    let b = (from a | take 1)
+    let c = (from b)
+    from a
+    "###).unwrap_err(), @r"
+    Error:
+       ╭─[:1:1]
+       │
+     1 │ ╭─▶
+     2 │ ├─▶     let a = (from c)
+       │ │
+       │ ╰────────────────────────── Circular reference between relations: `a` -> `c` -> `b` -> `a`
+    ───╯
+    ");
+}
+
 #[test]
 fn test_prql_to_sql_1() {
     assert_snapshot!(compile(r#"
@@ -3276,6 +4617,30 @@ fn test_ident_escaping() {
     "#);
 }
 
+#[test]
+fn test_ident_unicode() {
+    // Unicode letters don't need backticks, but are always quoted in SQL
+    let query = r#"
+    from 社員
+    select {名前, größe = height}
+    "#;
+
+    assert_snapshot!(compile_with_sql_dialect(query, sql::Dialect::Postgres).unwrap(), @r#"
+    SELECT
+      "名前",
+      height AS "größe"
+    FROM
+      "社員"
+    "#);
+    assert_snapshot!(compile_with_sql_dialect(query, sql::Dialect::MySql).unwrap(), @r"
+    SELECT
+      `名前`,
+      height AS `größe`
+    FROM
+      `社員`
+    ");
+}
+
 #[test]
 fn test_literal() {
     let query = r###"
@@ -3794,6 +5159,45 @@ fn test_group_all() {
         "###).unwrap_err(), @"Error: Excluding columns not supported as this position");
 }
 
+#[test]
+fn test_group_by_all() {
+    assert_snapshot!(compile(
+        r###"
+    prql target:sql.duckdb
+
+    from tracks
+    group all (aggregate {plays = count this})
+        "###).unwrap(), @r"
+    SELECT
+      *,
+      COUNT(*) AS plays
+    FROM
+      tracks
+    GROUP BY
+      ALL
+    ");
+
+    // with a known schema, other dialects list the columns
+    assert_snapshot!(compile(
+        r###"
+    prql target:sql.postgres
+
+    from tracks
+    select {album_id, genre_id}
+    group all (aggregate {plays = count this})
+        "###).unwrap(), @r"
+    SELECT
+      album_id,
+      genre_id,
+      COUNT(*) AS plays
+    FROM
+      tracks
+    GROUP BY
+      album_id,
+      genre_id
+    ");
+}
+
 #[test]
 fn test_output_column_deduplication() {
     // #1249
@@ -4541,39 +5945,199 @@ fn test_loop_2() {
         JOIN employees AS manager ON manager.employee_id = table_1.reports_to
     )
     SELECT
-      *
+      *
+    FROM
+      table_1 AS table_2
+    "
+    );
+}
+
+#[test]
+fn test_params() {
+    assert_snapshot!(compile(r#"
+    from invoices
+    select {i = this}
+    filter $1 <= i.date || i.date <= $2
+    select {
+        i.id,
+        i.total,
+    }
+    filter i.total > $3
+    "#).unwrap(),
+        @r"
+    SELECT
+      id,
+      total
+    FROM
+      invoices
+    WHERE
+      (
+        $1 <= date
+        OR date <= $2
+      )
+      AND total > $3
+    "
+    )
+}
+
+#[test]
+fn test_params_values() {
+    use prqlc::lr::Literal;
+
+    let query = r#"
+    from orders
+    filter customer_id == $customer_id && status == $status && total > $min_total
+    "#;
+
+    // values are escaped like any other literal
+    let options = Options::default()
+        .no_signature()
+        .with_param("customer_id", Literal::Integer(42))
+        .with_param("status", Literal::String("it's shipped".to_string()));
+    assert_snapshot!(prqlc::compile(query, &options).unwrap(), @r"
+    SELECT
+      *
+    FROM
+      orders
+    WHERE
+      customer_id = 42
+      AND status = 'it''s shipped'
+      AND total > $min_total
+    ");
+
+    // parameters are numbered by first appearance; bound ones are skipped
+    let query = r#"
+    from orders
+    filter customer_id == $customer_id || referrer_id == $customer_id
+    filter status == $status && created_at > $since
+    "#;
+    let (sql, params) = prqlc::compile_with_params(query, &options).unwrap();
+    assert_snapshot!(sql, @r"
+    SELECT
+      *
+    FROM
+      orders
+    WHERE
+      (
+        customer_id = 42
+        OR referrer_id = 42
+      )
+      AND status = 'it''s shipped'
+      AND created_at > $1
+    ");
+    assert_eq!(params, vec!["since"]);
+
+    let options = Options::default()
+        .no_signature()
+        .with_target(Target::Sql(Some(sql::Dialect::SQLite)));
+    let (sql, params) = prqlc::compile_with_params(query, &options).unwrap();
+    assert_snapshot!(sql, @r"
+    SELECT
+      *
+    FROM
+      orders
+    WHERE
+      (
+        customer_id = ?1
+        OR referrer_id = ?1
+      )
+      AND status = ?2
+      AND created_at > ?3
+    ");
+    assert_eq!(params, vec!["customer_id", "status", "since"]);
+
+    let options = options
+        .with_target(Target::Sql(Some(sql::Dialect::MySql)))
+        .with_display(prqlc::DisplayOptions::Plain);
+    let err = prqlc::compile_with_params(query, &options).unwrap_err();
+    assert_eq!(
+        err.inner[0].reason,
+        "this dialect does not support numbered parameters"
+    );
+}
+
+#[test]
+fn test_ident_quoting() {
+    use prqlc::sql::{IdentCase, IdentQuoting};
+
+    let query = r#"
+    from employees
+    select {first_name, Last_Name, `order`}
+    "#;
+
+    let options = Options::default().no_signature();
+    assert_snapshot!(prqlc::compile(query, &options).unwrap(), @r#"
+    SELECT
+      first_name,
+      "Last_Name",
+      "order"
     FROM
-      table_1 AS table_2
-    "
-    );
+      employees
+    "#);
+
+    let always = options.clone().with_ident_quoting(IdentQuoting::Always);
+    assert_snapshot!(prqlc::compile(query, &always).unwrap(), @r#"
+    SELECT
+      "first_name",
+      "Last_Name",
+      "order"
+    FROM
+      "employees"
+    "#);
+
+    // upper-case names are bare, unless they are keywords
+    let upper = options
+        .clone()
+        .with_target(Target::Sql(Some(sql::Dialect::Snowflake)))
+        .with_ident_case(IdentCase::Upper);
+    assert_snapshot!(prqlc::compile(query, &upper).unwrap(), @r#"
+    SELECT
+      FIRST_NAME,
+      LAST_NAME,
+      "ORDER"
+    FROM
+      EMPLOYEES
+    "#);
+
+    let lower = options
+        .with_ident_quoting(IdentQuoting::Never)
+        .with_ident_case(IdentCase::Lower);
+    assert_snapshot!(prqlc::compile(query, &lower).unwrap(), @r"
+    SELECT
+      first_name,
+      last_name,
+      order
+    FROM
+      employees
+    ");
 }
 
 #[test]
-fn test_params() {
-    assert_snapshot!(compile(r#"
-    from invoices
-    select {i = this}
-    filter $1 <= i.date || i.date <= $2
-    select {
-        i.id,
-        i.total,
-    }
-    filter i.total > $3
-    "#).unwrap(),
-        @r"
+fn test_simplify() {
+    let query = r#"
+    from t
+    filter true
+    filter x > 1
+    filter (z < 10 && true)
+    derive {a = 2 + 3 * 4, b = -(-x), c = !(!y)}
+    "#;
+
+    assert_snapshot!(compile(query).unwrap(), @r"
     SELECT
-      id,
-      total
+      *,
+      14 AS a,
+      x AS b,
+      y AS c
     FROM
-      invoices
+      t
     WHERE
-      (
-        $1 <= date
-        OR date <= $2
-      )
-      AND total > $3
-    "
-    )
+      x > 1
+      AND z < 10
+    ");
+
+    let options = Options::default().no_signature().no_simplify();
+    let sql = prqlc::compile(query, &options).unwrap();
+    assert!(sql.contains("2 + 3 * 4 AS a"));
 }
 
 // for #1969
@@ -4767,6 +6331,201 @@ fn test_regex_search() {
     );
 }
 
+#[test]
+fn test_regex_replace_extract() {
+    assert_snapshot!(compile_with_sql_dialect(r###"
+    from users
+    derive {
+      masked = regex_replace phone "\\d" "#",
+      area_code = regex_extract phone "^\\d{3}",
+    }
+    "###, sql::Dialect::Postgres).unwrap(), @r"
+    SELECT
+      *,
+      REGEXP_REPLACE(phone, '\d', '#', 'g') AS masked,
+      SUBSTRING(
+        phone
+        FROM
+          '^\d{3}'
+      ) AS area_code
+    FROM
+      users
+    ");
+
+    // MySQL treats a backslash in a string literal as an escape
+    assert_snapshot!(compile_with_sql_dialect(r###"
+    from users
+    derive {
+      masked = regex_replace phone "\\d" "#",
+      area_code = regex_extract phone "^\\d{3}",
+    }
+    "###, sql::Dialect::MySql).unwrap(), @r"
+    SELECT
+      *,
+      REGEXP_REPLACE(phone, '\\d', '#') AS masked,
+      REGEXP_SUBSTR(phone, '^\\d{3}') AS area_code
+    FROM
+      users
+    ");
+
+    assert_snapshot!(compile_with_sql_dialect(r###"
+    from users
+    derive {masked = regex_replace phone "\\d" "#"}
+    "###, sql::Dialect::SQLite).unwrap_err(), @r##"
+    Error:
+       ╭─[:3:22]
+       │
+     3 │     derive {masked = regex_replace phone "\\d" "#"}
+       │                      ──────────────┬──────────────
+       │                                    ╰──────────────── operator std.regex_replace is not supported for dialect sqlite
+    ───╯
+    "##);
+}
+
+#[test]
+fn test_is_json() {
+    assert_snapshot!(compile_with_sql_dialect(r###"
+    from events
+    filter (is_json payload) && (is_json_object metadata)
+    derive {has_tags = is_json_array tags}
+    "###, sql::Dialect::Postgres).unwrap(), @r"
+    SELECT
+      *,
+      tags IS JSON ARRAY AS has_tags
+    FROM
+      events
+    WHERE
+      payload IS JSON
+      AND metadata IS JSON OBJECT
+    ");
+
+    assert_snapshot!(compile_with_sql_dialect(r###"
+    from events
+    filter (is_json payload)
+    "###, sql::Dialect::SQLite).unwrap(), @r"
+    SELECT
+      *
+    FROM
+      events
+    WHERE
+      json_valid(payload)
+    ");
+
+    assert_snapshot!(compile_with_sql_dialect(r###"
+    from events
+    filter (is_json payload)
+    "###, sql::Dialect::BigQuery).unwrap_err(), @r"
+    Error:
+       ╭─[:3:13]
+       │
+     3 │     filter (is_json payload)
+       │             ───────┬───────
+       │                    ╰───────── operator std.is_json is not supported for dialect bigquery
+    ───╯
+    ");
+}
+
+#[test]
+fn test_hash() {
+    assert_snapshot!(compile_with_sql_dialect(r###"
+    from users
+    derive {email_md5 = md5 email, email_hash = hash email}
+    "###, sql::Dialect::Postgres).unwrap(), @r"
+    SELECT
+      *,
+      MD5(email) AS email_md5,
+      ENCODE(SHA256(CONVERT_TO(email, 'UTF8')), 'hex') AS email_hash
+    FROM
+      users
+    ");
+
+    assert_snapshot!(compile_with_sql_dialect(r###"
+    from users
+    derive {email_md5 = md5 email, email_hash = hash email}
+    "###, sql::Dialect::BigQuery).unwrap(), @r"
+    SELECT
+      *,
+      TO_HEX(MD5(email)) AS email_md5,
+      TO_HEX(SHA256(email)) AS email_hash
+    FROM
+      users
+    ");
+}
+
+#[test]
+fn test_assert() {
+    assert_snapshot!(compile(r###"
+    from employees
+    filter country == "USA"
+    assert ((count this) > 0)
+    select {name, salary}
+    "###).unwrap(), @r"
+    WITH table_0 AS (
+      SELECT
+        *
+      FROM
+        employees
+      WHERE
+        country = 'USA'
+    ),
+    table_1 AS (
+      SELECT
+        1 / CASE
+          WHEN COUNT(*) > 0 THEN 1
+          ELSE 0
+        END AS _assert
+      FROM
+        table_0
+    )
+    SELECT
+      table_0.name,
+      table_0.salary
+    FROM
+      table_0
+      JOIN table_1 ON table_1._assert = 1
+    ");
+
+    // a condition of a single row must hold for all rows
+    assert_snapshot!(compile_with_sql_dialect(r###"
+    from employees
+    assert (salary > 0)
+    "###, sql::Dialect::DuckDb).unwrap(), @r"
+    WITH table_0 AS (
+      SELECT
+        *
+      FROM
+        employees
+    ),
+    table_1 AS (
+      SELECT
+        CASE
+          WHEN COALESCE(BOOL_AND(salary > 0), TRUE) THEN 1
+          ELSE error('assertion failed')
+        END AS _assert
+      FROM
+        table_0
+    )
+    SELECT
+      table_0.*
+    FROM
+      table_0
+      JOIN table_1 ON table_1._assert = 1
+    ");
+
+    assert_snapshot!(compile_with_sql_dialect(r###"
+    from employees
+    assert (salary > 0)
+    "###, sql::Dialect::SQLite).unwrap_err(), @r"
+    Error:
+       ╭─[:3:5]
+       │
+     3 │     assert (salary > 0)
+       │     ─────────┬─────────
+       │              ╰─────────── operator std.assert is not supported for dialect sqlite
+    ───╯
+    ");
+}
+
 #[test]
 fn test_intervals() {
     assert_snapshot!(compile(r#"
@@ -5087,29 +6846,12 @@ fn test_returning_constants_only() {
     "###,
     )
     .unwrap(), @r"
-    WITH table_1 AS (
-      SELECT
-        NULL
-      FROM
-        tb1
-      LIMIT
-        10
-    ), table_0 AS (
-      SELECT
-        NULL
-      FROM
-        table_1
-      WHERE
-        true
-      LIMIT
-        20
-    )
     SELECT
       10 AS d
     FROM
-      table_0
-    WHERE
-      true
+      tb1
+    LIMIT
+      10
     ");
 }
 
@@ -5369,4 +7111,152 @@ fn test_import() {
     FROM
       x
     ");
+
+    assert_snapshot!(compile(
+        r###"
+    module hello {
+        let world = 1
+    }
+
+    import hello
+
+    from x | select hello.world
+        "###,
+    )
+    .unwrap(), @r"
+    SELECT
+      1
+    FROM
+      x
+    ");
+}
+
+#[test]
+fn test_multiple_queries() {
+    assert_snapshot!(compile(
+        r###"
+    let managers = (from employees | filter is_manager)
+
+    from managers
+    select {name};
+
+    from departments
+    aggregate {count this}
+        "###,
+    )
+    .unwrap(), @r"
+    WITH managers AS (
+      SELECT
+        *
+      FROM
+        employees
+      WHERE
+        is_manager
+    )
+    SELECT
+      name
+    FROM
+      managers;
+
+    SELECT
+      COUNT(*)
+    FROM
+      departments
+    ");
+
+    let opts = Options::default().no_signature().with_format(false);
+    assert_snapshot!(prqlc::compile("from a; from b", &opts).unwrap(), @"SELECT * FROM a; SELECT * FROM b");
+}
+
+#[test]
+fn test_source_resolver() {
+    use prqlc::ir::rq;
+    use prqlc::pr::Ident;
+    use prqlc::sql::{ResolvedSource, SourceResolver};
+
+    struct Warehouse;
+
+    impl SourceResolver for Warehouse {
+        fn resolve_source(&self, name: &Ident) -> prqlc::Result<ResolvedSource> {
+            match name.name.as_str() {
+                "employees" => Ok(ResolvedSource::Table(Ident::from_path(vec![
+                    "hr",
+                    "employees_v2",
+                ]))),
+                "regions" => Ok(ResolvedSource::Relation(rq::RelationKind::Literal(
+                    rq::RelationLiteral {
+                        columns: vec!["region_id".to_string(), "name".to_string()],
+                        rows: vec![vec![
+                            prqlc::lr::Literal::Integer(1),
+                            prqlc::lr::Literal::String("north".to_string()),
+                        ]],
+                    },
+                ))),
+                _ => Err(prqlc::Error::new_simple(format!("unknown table {name}"))),
+            }
+        }
+    }
+
+    let compile_with_resolver = |prql: &str| {
+        let rq = prqlc::prql_to_pl(prql).and_then(prqlc::pl_to_rq)?;
+        let opts = Options::default().no_signature();
+        sql::compile_with_source_resolver(vec![rq], &opts, &Warehouse).map_err(ErrorMessages::from)
+    };
+
+    assert_snapshot!(compile_with_resolver(r###"
+    from employees
+    join regions (==region_id)
+    select {employees.name, region = regions.name}
+    "###).unwrap(), @r"
+    WITH table_0 AS (
+      SELECT
+        1 AS region_id,
+        'north' AS name
+    )
+    SELECT
+      employees.name,
+      regions.name AS region
+    FROM
+      hr.employees_v2 AS employees
+      JOIN table_0 AS regions ON employees.region_id = regions.region_id
+    ");
+
+    assert_snapshot!(compile_with_resolver(r###"
+    from salaries
+    "###).unwrap_err(), @"Error: unknown table salaries");
+}
+
+#[test]
+fn test_compile_fragment() {
+    let opts = Options::default().no_signature();
+    let compile_fragment = |prql: &str| prqlc::compile_fragment(prql, &opts);
+
+    assert_snapshot!(compile_fragment("age > 18 && active").unwrap(), @"age > 18 AND active");
+    assert_snapshot!(compile_fragment("(salary + bonus) * 12 | math.round 2").unwrap(), @"ROUND((salary + bonus) * 12, 2)");
+
+    // in the dialect of the target
+    let sqlite = opts
+        .clone()
+        .with_target(Target::Sql(Some(sql::Dialect::SQLite)));
+    assert_snapshot!(prqlc::compile_fragment(r#"name | text.starts_with "A""#, &sqlite).unwrap(), @"name LIKE 'A' || '%'");
+
+    assert_snapshot!(compile_fragment("age > ").unwrap_err(), @r"
+    Error:
+       ╭─[:1:6]
+       │
+     1 │ age >
+       │      │
+       │      ╰─ Expected one of !, (, +, -, ==, [, an identifier, keyword case, keyword internal or {, but didn't find anything before the end.
+    ───╯
+    ");
+    assert_snapshot!(compile_fragment("age | math.nope").unwrap_err(), @r"
+    Error:
+       ╭─[:1:7]
+       │
+     1 │ age | math.nope
+       │       ────┬────
+       │           ╰────── Unknown name `math.nope`
+    ───╯
+    ");
+    assert_snapshot!(compile_fragment("let x = 1").unwrap_err(), @"Error: expected an expression");
 }